@@ -0,0 +1,122 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+pub const ARCHLIST_FILE: &str = "archlist";
+
+/// Where new URLs go in the archlist. Bottom-up processing expects new URLs at the
+/// top, so that the saved position counted from the bottom stays valid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Placement {
+    Top,
+    Bottom,
+}
+
+/// Normalizes a repository URL for duplicate detection: lowercase scheme and host,
+/// no query or fragment, no trailing slash or `.git` suffix.
+pub fn normalize_url(url: &str) -> String {
+    let url = url.trim();
+
+    match url::Url::parse(url) {
+        Ok(parsed) => {
+            let path = parsed.path().trim_end_matches('/');
+            let path = path.strip_suffix(".git").unwrap_or(path);
+            format!(
+                "{}://{}{}",
+                parsed.scheme(),
+                parsed.host_str().unwrap_or_default(),
+                path
+            )
+        }
+        Err(_) => url.trim_end_matches('/').to_string(),
+    }
+}
+
+/// Reads the normalized set of URLs in the archlist, ignoring blanks and comments.
+/// A missing archlist is treated as empty.
+pub fn read_entries(path: &Path) -> Result<HashSet<String>> {
+    if !path.exists() {
+        return Ok(HashSet::new());
+    }
+
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read archlist: {}", path.display()))?;
+
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(normalize_url)
+        .collect())
+}
+
+/// Returns the candidates not already in `existing`, deduplicated, in input order.
+pub fn new_urls(existing: &HashSet<String>, candidates: &[String]) -> Vec<String> {
+    let mut seen = existing.clone();
+
+    candidates
+        .iter()
+        .filter(|url| seen.insert(normalize_url(url)))
+        .map(|url| url.trim().to_string())
+        .collect()
+}
+
+/// Adds the candidates not already present to the archlist, returning the added URLs.
+pub fn add_urls(path: &Path, candidates: &[String], placement: Placement) -> Result<Vec<String>> {
+    let existing = read_entries(path)?;
+    let added = new_urls(&existing, candidates);
+
+    if added.is_empty() {
+        return Ok(added);
+    }
+
+    let content = if path.exists() {
+        fs::read_to_string(path)
+            .with_context(|| format!("Failed to read archlist: {}", path.display()))?
+    } else {
+        String::new()
+    };
+
+    let mut new_lines = added.join("\n");
+    new_lines.push('\n');
+
+    let updated = match placement {
+        Placement::Top => new_lines + &content,
+        Placement::Bottom if content.is_empty() || content.ends_with('\n') => content + &new_lines,
+        Placement::Bottom => content + "\n" + &new_lines,
+    };
+
+    // Write beside the archlist and rename, so an interrupted write can't truncate it
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, updated)
+        .with_context(|| format!("Failed to write archlist: {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, path)
+        .with_context(|| format!("Failed to replace archlist: {}", path.display()))?;
+
+    Ok(added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_url() {
+        assert_eq!(normalize_url("https://GitHub.com/rust-lang/rust/"), "https://github.com/rust-lang/rust");
+        assert_eq!(normalize_url("https://github.com/rust-lang/rust.git"), "https://github.com/rust-lang/rust");
+        assert_eq!(normalize_url(" https://github.com/rust-lang/rust?tab=readme "), "https://github.com/rust-lang/rust");
+    }
+
+    #[test]
+    fn test_new_urls_skips_existing_and_repeats() {
+        let existing: HashSet<String> = ["https://github.com/a/b".to_string()].into_iter().collect();
+        let candidates = vec![
+            "https://github.com/a/b/".to_string(),
+            "https://github.com/a/c".to_string(),
+            "https://github.com/a/c.git".to_string(),
+        ];
+
+        assert_eq!(new_urls(&existing, &candidates), vec!["https://github.com/a/c"]);
+    }
+}
@@ -0,0 +1,150 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Identifies the archlist a saved position belongs to. The hash covers only the
+/// lines already processed, so new URLs added at the unprocessed end don't
+/// invalidate the position, but edits to processed lines do.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ArchlistFingerprint {
+    pub size: u64,
+    pub modified: Option<i64>,
+    pub consumed_hash: String,
+}
+
+/// Stable FNV-1a hash over archlist lines, in processing order.
+#[derive(Debug, Clone, Copy)]
+pub struct LineHasher(u64);
+
+impl LineHasher {
+    pub fn new() -> Self {
+        Self(FNV_OFFSET_BASIS)
+    }
+
+    pub fn add_line(&mut self, line: &str) {
+        for byte in line.bytes().chain(std::iter::once(b'\n')) {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    pub fn hex(&self) -> String {
+        format!("{:016x}", self.0)
+    }
+}
+
+impl Default for LineHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Size and modification time of the archlist, recorded alongside the hash.
+pub fn file_stamp(path: &Path) -> Result<(u64, Option<i64>)> {
+    let metadata = std::fs::metadata(path)
+        .with_context(|| format!("Failed to stat archlist: {}", path.display()))?;
+
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64);
+
+    Ok((metadata.len(), modified))
+}
+
+/// Low-water mark over lines that complete out of order. The position only
+/// advances past a line once it and every line before it have completed, so a
+/// crash never skips a URL that was still in flight.
+#[derive(Debug)]
+pub struct Checkpoint {
+    base: usize,
+    lines: Arc<[String]>,
+    next: usize,
+    done: BTreeSet<usize>,
+    hasher: LineHasher,
+}
+
+impl Checkpoint {
+    /// `base` is the number of lines skipped from a previous run and `hasher` their
+    /// hash; `lines` are the lines to be processed in this run, in order, shared
+    /// with the run rather than copied.
+    pub fn new(base: usize, hasher: LineHasher, lines: Arc<[String]>) -> Self {
+        Self {
+            base,
+            lines,
+            next: 0,
+            done: BTreeSet::new(),
+            hasher,
+        }
+    }
+
+    pub fn complete(&mut self, index: usize) {
+        if index < self.next {
+            return;
+        }
+        self.done.insert(index);
+
+        while self.done.remove(&self.next) {
+            if let Some(line) = self.lines.get(self.next) {
+                self.hasher.add_line(line);
+            }
+            self.next += 1;
+        }
+    }
+
+    /// Lines fully processed, counting those skipped from earlier runs.
+    pub fn position(&self) -> usize {
+        self.base + self.next
+    }
+
+    pub fn consumed_hash(&self) -> String {
+        self.hasher.hex()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(n: usize) -> Arc<[String]> {
+        (0..n).map(|i| format!("https://github.com/o/r{}", i)).collect()
+    }
+
+    #[test]
+    fn test_checkpoint_waits_for_contiguous_completion() {
+        let mut checkpoint = Checkpoint::new(5, LineHasher::new(), lines(4));
+
+        checkpoint.complete(1);
+        checkpoint.complete(3);
+        assert_eq!(checkpoint.position(), 5);
+
+        checkpoint.complete(0);
+        assert_eq!(checkpoint.position(), 7);
+
+        checkpoint.complete(2);
+        assert_eq!(checkpoint.position(), 9);
+    }
+
+    #[test]
+    fn test_consumed_hash_matches_sequential_hash() {
+        let lines = lines(3);
+        let mut checkpoint = Checkpoint::new(0, LineHasher::new(), Arc::clone(&lines));
+        checkpoint.complete(2);
+        checkpoint.complete(0);
+        checkpoint.complete(1);
+
+        let mut hasher = LineHasher::new();
+        for line in lines.iter() {
+            hasher.add_line(line);
+        }
+
+        assert_eq!(checkpoint.consumed_hash(), hasher.hex());
+    }
+}
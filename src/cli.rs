@@ -1,4 +1,6 @@
-use clap::{Parser, Subcommand};
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
+use crate::provider::strategy::StrategyKind;
+use std::path::PathBuf;
 use clap_complete::Shell;
 
 #[derive(Parser)]
@@ -10,21 +12,470 @@ pub struct Cli {
 
     #[arg(long, help = "Generate shell completion for the given shell")]
     pub completions: Option<Shell>,
+
+    #[arg(
+        short,
+        long,
+        global = true,
+        action = ArgAction::Count,
+        help = "Show debug diagnostics, -vv for trace. RUST_LOG overrides this"
+    )]
+    pub verbose: u8,
+
+    #[arg(
+        short,
+        long,
+        global = true,
+        conflicts_with = "verbose",
+        help = "Only show errors, no progress"
+    )]
+    pub quiet: bool,
+
+    #[arg(long, global = true, help = "Also write progress and diagnostics to this file")]
+    pub log_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        default_value_t = 10,
+        help = "Rotate the log file once it reaches this many MiB, 0 to never rotate"
+    )]
+    pub log_max_mb: u64,
+
+    #[arg(long, global = true, default_value_t = 5, help = "Number of rotated log files to keep")]
+    pub log_keep: usize,
+
+    #[command(flatten)]
+    pub limits: LimitArgs,
+}
+
+impl Cli {
+    pub fn log_options(&self) -> crate::logging::LogOptions {
+        crate::logging::LogOptions {
+            verbose: self.verbose,
+            quiet: self.quiet,
+            log_file: self.log_file.clone(),
+            log_max_bytes: self.log_max_mb * 1024 * 1024,
+            log_keep: self.log_keep,
+        }
+    }
+}
+
+/// Overrides for the `[limits]` and `[domains]` config tables.
+#[derive(Args)]
+#[command(next_help_heading = "Limits")]
+pub struct LimitArgs {
+    #[arg(long, global = true, help = "URLs processed at once")]
+    pub concurrency: Option<usize>,
+
+    #[arg(long, global = true, help = "Requests in flight across all domains")]
+    pub max_concurrent_global: Option<usize>,
+
+    #[arg(long, global = true, help = "Requests in flight per domain")]
+    pub max_concurrent_per_domain: Option<usize>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "HOST=N",
+        value_parser = parse_domain_limit,
+        help = "Requests in flight for one domain, e.g. github.com=4. Repeatable"
+    )]
+    pub domain_concurrency: Vec<(String, usize)>,
+
+    #[arg(long, global = true, value_name = "SECS", help = "Smoother micro-interval")]
+    pub smoother_interval: Option<u64>,
+
+    #[arg(long, global = true, help = "Smoother velocity, the multiple of realtime a quota is spent at")]
+    pub velocity: Option<f64>,
+
+    #[arg(long, global = true, value_name = "SECS", help = "Whole-request timeout, 0 for none")]
+    pub request_timeout: Option<u64>,
+
+    #[arg(long, global = true, value_name = "SECS", help = "Connection timeout, 0 for none")]
+    pub connect_timeout: Option<u64>,
+}
+
+impl LimitArgs {
+    /// Applies the flags given on top of the layered settings.
+    pub fn apply(&self, config: &mut crate::config::AppConfig) {
+        let limits = &mut config.limits;
+        if let Some(concurrency) = self.concurrency {
+            limits.concurrency = concurrency;
+        }
+        if let Some(max) = self.max_concurrent_global {
+            limits.max_concurrent_global = max;
+        }
+        if let Some(max) = self.max_concurrent_per_domain {
+            limits.max_concurrent_per_domain = max;
+        }
+        if let Some(interval) = self.smoother_interval {
+            limits.micro_interval_secs = interval;
+        }
+        if let Some(velocity) = self.velocity {
+            limits.velocity = velocity;
+        }
+        if let Some(timeout) = self.request_timeout {
+            limits.request_timeout_secs = timeout;
+        }
+        if let Some(timeout) = self.connect_timeout {
+            limits.connect_timeout_secs = timeout;
+        }
+
+        for (domain, max) in &self.domain_concurrency {
+            config.domains.entry(domain.clone()).or_default().max_concurrent = Some(*max);
+        }
+    }
+
+    /// Setting keys the flags given override, for `config show`.
+    pub fn keys(&self) -> Vec<String> {
+        let given = [
+            ("limits.concurrency", self.concurrency.is_some()),
+            ("limits.max_concurrent_global", self.max_concurrent_global.is_some()),
+            ("limits.max_concurrent_per_domain", self.max_concurrent_per_domain.is_some()),
+            ("limits.micro_interval_secs", self.smoother_interval.is_some()),
+            ("limits.velocity", self.velocity.is_some()),
+            ("limits.request_timeout_secs", self.request_timeout.is_some()),
+            ("limits.connect_timeout_secs", self.connect_timeout.is_some()),
+        ];
+
+        let mut keys: Vec<String> = given
+            .into_iter()
+            .filter(|(_, given)| *given)
+            .map(|(key, _)| key.to_string())
+            .collect();
+        for (domain, _) in &self.domain_concurrency {
+            keys.push(crate::config::format_key(&["domains", domain, "max_concurrent"]));
+        }
+        keys
+    }
 }
 
 #[derive(Subcommand)]
 pub enum Commands {
     ReadmeGet(ReadmeGetArgs),
+    AddOrg(AddOrgArgs),
+    ReadHistory(ReadHistoryArgs),
+    RetryFailed(RetryFailedArgs),
+    Status(StatusArgs),
+    Get(GetArgs),
+    Explain(ExplainArgs),
+    Config(ConfigArgs),
+}
+
+#[derive(Args)]
+pub struct ArchlistArgs {
+    #[arg(
+        long,
+        help = "Archlist file to use instead of searching ./archlist, ~/.config/archlist and ~/.config/archlist/archlist"
+    )]
+    pub archlist: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct OutputArgs {
+    #[arg(long, default_value = ".", help = "Directory README files are written into")]
+    pub output_dir: PathBuf,
+
+    #[arg(long, help = "JSONL failure ledger [default: <output-dir>/failures.jsonl]")]
+    pub ledger: Option<PathBuf>,
+}
+
+impl OutputArgs {
+    pub fn ledger_path(&self) -> PathBuf {
+        self.ledger
+            .clone()
+            .unwrap_or_else(|| self.output_dir.join(crate::failure::LEDGER_FILE))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Progress lines for people
+    #[default]
+    Text,
+    /// One JSON event per line on stdout
+    Jsonl,
 }
 
 #[derive(Parser)]
 pub struct ReadmeGetArgs {
+    #[command(flatten)]
+    pub archlist: ArchlistArgs,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+
+    #[arg(
+        long,
+        help = "Also export current failures as `<url> <code>` lines to this file at the end of the run"
+    )]
+    pub fail_file: Option<PathBuf>,
+
     #[arg(
+        short = 'T',
         long,
         help = "Process archlist from top to bottom instead of bottom to top"
     )]
     pub top_down: bool,
 
-    #[arg(long, help = "Refresh all URLs even if already downloaded")]
+    #[arg(short, long, help = "Refresh all URLs even if already downloaded")]
     pub refresh: bool,
+
+    #[arg(
+        long,
+        help = "Print the requests, token quotas and estimated time the run would take, without fetching"
+    )]
+    pub plan: bool,
+
+    #[arg(long, help = "Show a live progress view, when stdout is a terminal")]
+    pub progress: bool,
+
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text, help = "Output format")]
+    pub format: OutputFormat,
+
+    #[arg(long, help = "Stop scheduling URLs once more than this many have failed")]
+    pub max_failures: Option<usize>,
+}
+
+#[derive(Parser)]
+pub struct AddOrgArgs {
+    #[command(flatten)]
+    pub archlist: ArchlistArgs,
+
+    #[arg(help = "Organization, group or author URL, e.g. https://github.com/rust-lang")]
+    pub url: String,
+
+    #[arg(long, help = "Include forked repositories")]
+    pub include_forks: bool,
+
+    #[arg(long, help = "Include archived repositories")]
+    pub include_archived: bool,
+
+    #[arg(long, help = "Print the URLs that would be added without changing the archlist")]
+    pub dry_run: bool,
+
+    #[arg(
+        long,
+        help = "Add new URLs at the bottom of the archlist instead of the top, for top-down processing"
+    )]
+    pub append: bool,
+}
+
+#[derive(Parser)]
+pub struct ReadHistoryArgs {
+    #[command(flatten)]
+    pub archlist: ArchlistArgs,
+
+    #[arg(
+        required = true,
+        help = "Firefox or Chromium profile directory, or a places.sqlite / History file"
+    )]
+    pub profiles: Vec<PathBuf>,
+
+    #[arg(long, help = "Add candidates to the top of the archlist instead of printing them")]
+    pub add: bool,
+
+    #[arg(long, help = "Add candidates to the bottom of the archlist, for top-down processing")]
+    pub append: bool,
+
+    #[arg(long, default_value_t = 1, help = "Only consider repos visited at least this many times")]
+    pub min_visits: u64,
+
+    #[arg(long, help = "Maximum number of candidates")]
+    pub limit: Option<usize>,
+}
+
+#[derive(Parser)]
+pub struct RetryFailedArgs {
+    #[command(flatten)]
+    pub output: OutputArgs,
+
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Only retry failures with these error codes, e.g. RATE-LIMIT,UNKNOWN"
+    )]
+    pub code: Vec<String>,
+
+    #[arg(
+        long,
+        value_parser = parse_age,
+        help = "Only retry failures recorded at least this long ago, e.g. 30m, 12h, 1d, 2w"
+    )]
+    pub older_than: Option<chrono::Duration>,
+
+    #[arg(long, help = "Show a live progress view, when stdout is a terminal")]
+    pub progress: bool,
+
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text, help = "Output format")]
+    pub format: OutputFormat,
+
+    #[arg(long, help = "Stop scheduling URLs once more than this many have failed")]
+    pub max_failures: Option<usize>,
+}
+
+#[derive(Parser)]
+pub struct StatusArgs {
+    #[command(flatten)]
+    pub archlist: ArchlistArgs,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+
+    #[arg(short = 'T', long, help = "Report against the top-down position instead of bottom-up")]
+    pub top_down: bool,
+
+    #[arg(long, help = "Print the report as JSON")]
+    pub json: bool,
+}
+
+#[derive(Parser)]
+pub struct GetArgs {
+    #[arg(help = "Repository URL")]
+    pub url: String,
+
+    #[arg(short, long, help = "Write the README to this file instead of stdout")]
+    pub output: Option<PathBuf>,
+
+    #[arg(long, value_enum, help = "Use only this strategy")]
+    pub strategy: Option<StrategyKind>,
+
+    #[arg(long, help = "Print the resolved README URL to stderr")]
+    pub print_url: bool,
+}
+
+#[derive(Parser)]
+pub struct ExplainArgs {
+    #[arg(help = "Repository URL")]
+    pub url: String,
+}
+
+#[derive(Parser)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub action: ConfigAction,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Print the effective settings, where each came from, and the archlist's state
+    Show(ArchlistArgs),
+    /// Print one setting, e.g. limits.concurrency or domains."github.com".env_var
+    Get(ConfigGetArgs),
+    /// Write one setting to the user config file, or the project file
+    Set(ConfigSetArgs),
+    /// Print the config and state file paths
+    Path(ArchlistArgs),
+    /// Start the archlist over on the next run
+    ResetPosition(ResetPositionArgs),
+}
+
+#[derive(Parser)]
+pub struct ConfigGetArgs {
+    #[arg(help = "Setting key, e.g. limits.concurrency")]
+    pub key: String,
+}
+
+#[derive(Parser)]
+pub struct ConfigSetArgs {
+    #[arg(help = "Setting key, e.g. limits.concurrency")]
+    pub key: String,
+
+    #[arg(help = "Value as TOML, e.g. 20, 1.5 or '[\"api\", \"raw-git\"]'. Other text is a string")]
+    pub value: String,
+
+    #[arg(
+        long,
+        help = "Write to the nearest .archive-list.toml, or create one in the working directory"
+    )]
+    pub project: bool,
+}
+
+#[derive(Parser)]
+pub struct ResetPositionArgs {
+    #[command(flatten)]
+    pub archlist: ArchlistArgs,
+
+    #[arg(short = 'T', long, conflicts_with = "bottom_up", help = "Only reset the top-down position")]
+    pub top_down: bool,
+
+    #[arg(long, help = "Only reset the bottom-up position")]
+    pub bottom_up: bool,
+}
+
+/// Parses an age like `90s`, `30m`, `12h`, `1d` or `2w`.
+pub fn parse_age(value: &str) -> Result<chrono::Duration, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+
+    let number: i64 = number
+        .parse()
+        .map_err(|_| format!("invalid age '{}', expected e.g. 30m, 12h, 1d", value))?;
+
+    let age = match unit {
+        "s" => chrono::Duration::try_seconds(number),
+        "m" => chrono::Duration::try_minutes(number),
+        "h" => chrono::Duration::try_hours(number),
+        "d" => chrono::Duration::try_days(number),
+        "w" => chrono::Duration::try_weeks(number),
+        _ => return Err(format!("unknown unit '{}' in age '{}', use s, m, h, d or w", unit, value)),
+    };
+
+    age.ok_or_else(|| format!("age too large: '{}'", value))
+}
+
+/// Parses a `HOST=N` per-domain concurrency override.
+pub fn parse_domain_limit(value: &str) -> Result<(String, usize), String> {
+    let (host, max) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected HOST=N, got '{}'", value))?;
+
+    let host = host.trim().to_lowercase();
+    if host.is_empty() {
+        return Err(format!("missing host in '{}'", value));
+    }
+
+    let max: usize = max
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid count in '{}', expected e.g. github.com=4", value))?;
+    if max == 0 {
+        return Err(format!("count in '{}' must be at least 1", value));
+    }
+
+    Ok((host, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_age_units() {
+        assert_eq!(parse_age("90s").unwrap(), chrono::Duration::seconds(90));
+        assert_eq!(parse_age("30m").unwrap(), chrono::Duration::minutes(30));
+        assert_eq!(parse_age("12h").unwrap(), chrono::Duration::hours(12));
+        assert_eq!(parse_age("1d").unwrap(), chrono::Duration::days(1));
+        assert_eq!(parse_age("2w").unwrap(), chrono::Duration::weeks(2));
+        assert!(parse_age("1y").is_err());
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("12").is_err());
+        assert!(parse_age("9223372036854775807w").is_err());
+    }
+
+    #[test]
+    fn test_parse_domain_limit() {
+        assert_eq!(
+            parse_domain_limit("GitHub.com=4").unwrap(),
+            ("github.com".to_string(), 4)
+        );
+        assert!(parse_domain_limit("github.com").is_err());
+        assert!(parse_domain_limit("=4").is_err());
+        assert!(parse_domain_limit("github.com=0").is_err());
+        assert!(parse_domain_limit("github.com=x").is_err());
+    }
 }
@@ -0,0 +1,53 @@
+use crate::archlist::{self, Placement, ARCHLIST_FILE};
+use crate::cli::AddOrgArgs;
+use crate::logging::progress;
+use crate::provider::listing::{list_repos, ListOptions};
+use crate::util::get_provider_factory;
+use crate::config::ConfigManager;
+use anyhow::Result;
+use std::path::PathBuf;
+
+pub async fn add_org(args: AddOrgArgs) -> Result<()> {
+    let archlist_path = ConfigManager::new()?
+        .find_archlist(args.archlist.archlist.as_deref())
+        .unwrap_or_else(|| PathBuf::from(ARCHLIST_FILE));
+
+    let factory = get_provider_factory().await;
+    let provider = factory.get_provider(&args.url).await?;
+
+    let options = ListOptions {
+        include_forks: args.include_forks,
+        include_archived: args.include_archived,
+    };
+
+    let repos = list_repos(&provider, &args.url, &options).await?;
+    let urls: Vec<String> = repos.into_iter().map(|repo| repo.url).collect();
+
+    if args.dry_run {
+        let existing = archlist::read_entries(&archlist_path)?;
+        for url in archlist::new_urls(&existing, &urls) {
+            println!("{}", url);
+        }
+        return Ok(());
+    }
+
+    let placement = if args.append {
+        Placement::Bottom
+    } else {
+        Placement::Top
+    };
+
+    let added = archlist::add_urls(&archlist_path, &urls, placement)?;
+    for url in &added {
+        progress!("Added {}", url);
+    }
+
+    progress!(
+        "Added {} of {} repositories from {}",
+        added.len(),
+        urls.len(),
+        args.url
+    );
+
+    Ok(())
+}
@@ -0,0 +1,251 @@
+use crate::cli::{ArchlistArgs, ConfigAction, ConfigArgs, ConfigSetArgs, LimitArgs, ResetPositionArgs};
+use crate::config::{
+    check_key, env_key, flatten, format_key, parse_key, ConfigManager, PROJECT_CONFIG_FILE,
+};
+use crate::logging::progress;
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// One effective setting and the layer it came from.
+struct Setting {
+    path: Vec<String>,
+    value: toml::Value,
+    source: String,
+}
+
+pub fn config_command(args: ConfigArgs, limits: &LimitArgs) -> Result<()> {
+    let manager = ConfigManager::new()?;
+
+    match args.action {
+        ConfigAction::Show(archlist) => show(&manager, limits, &archlist),
+        ConfigAction::Get(args) => get(&manager, limits, &args.key),
+        ConfigAction::Set(args) => set(&manager, &args),
+        ConfigAction::Path(archlist) => path(&manager, &archlist),
+        ConfigAction::ResetPosition(args) => reset_position(&manager, &args),
+    }
+}
+
+fn show(manager: &ConfigManager, limits: &LimitArgs, archlist: &ArchlistArgs) -> Result<()> {
+    let settings = effective_settings(manager, limits)?;
+
+    let lines: Vec<(String, &str)> = settings
+        .iter()
+        .map(|setting| {
+            (
+                format!("{} = {}", format_key(&setting.path), setting.value),
+                setting.source.as_str(),
+            )
+        })
+        .collect();
+    let width = lines.iter().map(|(line, _)| line.len()).max().unwrap_or(0);
+    for (line, source) in &lines {
+        println!("{:<width$}  # {}", line, source, width = width);
+    }
+
+    if let Some(archlist_path) = manager.find_archlist(archlist.archlist.as_deref()) {
+        let state = manager.peek_state(&archlist_path)?;
+        println!();
+        println!(
+            "# state of {} in {}",
+            archlist_path.display(),
+            manager.state_path(&archlist_path)?.display()
+        );
+        println!("lines_from_bottom = {}", state.lines_from_bottom);
+        println!("lines_from_top = {}", state.lines_from_top);
+    }
+
+    Ok(())
+}
+
+fn get(manager: &ConfigManager, limits: &LimitArgs, key: &str) -> Result<()> {
+    let path = parse_key(key)?;
+    check_key(&path)?;
+
+    let settings = effective_settings(manager, limits)?;
+    let setting = settings
+        .iter()
+        .find(|setting| setting.path == path)
+        .with_context(|| format!("{} is not set", format_key(&path)))?;
+
+    println!("{}", setting.value);
+    log::debug!("{} from {}", format_key(&path), setting.source);
+    Ok(())
+}
+
+fn set(manager: &ConfigManager, args: &ConfigSetArgs) -> Result<()> {
+    let path = parse_key(&args.key)?;
+    check_key(&path)?;
+    if path == ["version"] {
+        bail!("version is managed by archive-list and can't be set");
+    }
+
+    let file = if args.project {
+        match manager.project_config_path() {
+            Some(file) => file,
+            None => std::env::current_dir()?.join(PROJECT_CONFIG_FILE),
+        }
+    } else {
+        // Loading migrates an old user config before it is edited
+        manager.load()?;
+        manager.config_path().to_path_buf()
+    };
+
+    let mut table = if file.exists() {
+        manager.read_file(&file)?
+    } else {
+        toml::Table::new()
+    };
+    set_path(&mut table, &path, parse_value(&args.value))?;
+    manager
+        .save(&file, &table)
+        .with_context(|| format!("Not saving {} = {}", format_key(&path), args.value))?;
+
+    progress!("Set {} in {}", format_key(&path), file.display());
+    Ok(())
+}
+
+fn path(manager: &ConfigManager, archlist: &ArchlistArgs) -> Result<()> {
+    println!("user config:    {}", manager.config_path().display());
+    println!(
+        "project config: {}",
+        manager
+            .project_config_path()
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|| "-".to_string())
+    );
+
+    match manager.find_archlist(archlist.archlist.as_deref()) {
+        Some(archlist_path) => println!(
+            "state:          {} (for {})",
+            manager.state_path(&archlist_path)?.display(),
+            archlist_path.display()
+        ),
+        None => println!("state:          - (no archlist found)"),
+    }
+
+    Ok(())
+}
+
+fn reset_position(manager: &ConfigManager, args: &ResetPositionArgs) -> Result<()> {
+    let archlist_path: PathBuf = manager
+        .find_archlist(args.archlist.archlist.as_deref())
+        .context("No archlist found")?;
+
+    let mut state = manager.load_state(&archlist_path)?;
+    if !args.top_down {
+        state.lines_from_bottom = 0;
+        state.bottom_fingerprint = None;
+    }
+    if !args.bottom_up {
+        state.lines_from_top = 0;
+        state.top_fingerprint = None;
+    }
+    manager.save_state(&archlist_path, &state)?;
+
+    let which = match (args.top_down, args.bottom_up) {
+        (true, _) => "top-down position",
+        (_, true) => "bottom-up position",
+        _ => "positions",
+    };
+    progress!("Reset {} of {}", which, archlist_path.display());
+    Ok(())
+}
+
+/// Every effective setting, CLI flags included, with the last layer that set it:
+/// the user or project config file, an `ARCHIVE_LIST_*` env var, or a flag.
+fn effective_settings(manager: &ConfigManager, limits: &LimitArgs) -> Result<Vec<Setting>> {
+    let mut config = manager.load()?;
+    limits.apply(&mut config);
+
+    let mut sources: HashMap<Vec<String>, String> = HashMap::new();
+    for file in [Some(manager.config_path().to_path_buf()), manager.project_config_path()]
+        .into_iter()
+        .flatten()
+        .filter(|file| file.exists())
+    {
+        for (path, _) in flatten(&manager.read_file(&file)?) {
+            sources.insert(path, file.display().to_string());
+        }
+    }
+    for (name, _) in std::env::vars() {
+        if let Some(path) = env_key(&name) {
+            sources.insert(path, format!("env {}", name));
+        }
+    }
+    for key in limits.keys() {
+        sources.insert(parse_key(&key)?, "command line".to_string());
+    }
+
+    let effective = toml::Table::try_from(&config)?;
+    Ok(flatten(&effective)
+        .into_iter()
+        .map(|(path, value)| Setting {
+            source: sources
+                .get(&path)
+                .cloned()
+                .unwrap_or_else(|| "default".to_string()),
+            path,
+            value,
+        })
+        .collect())
+}
+
+/// Parses a command-line value as TOML, e.g. `20`, `1.5` or `["api", "raw-git"]`,
+/// falling back to the text as a string.
+fn parse_value(raw: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {}", raw))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+}
+
+/// Sets `value` at `path` in `table`, creating intermediate tables.
+fn set_path(table: &mut toml::Table, path: &[String], value: toml::Value) -> Result<()> {
+    let (last, parents) = path.split_last().context("Empty setting key")?;
+
+    let mut current = table;
+    for segment in parents {
+        current = match current
+            .entry(segment.clone())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+        {
+            toml::Value::Table(inner) => inner,
+            _ => bail!("{} is not a table", segment),
+        };
+    }
+    current.insert(last.clone(), value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_value() {
+        assert_eq!(parse_value("20"), toml::Value::Integer(20));
+        assert_eq!(parse_value("1.5"), toml::Value::Float(1.5));
+        assert_eq!(parse_value("gitlab"), toml::Value::String("gitlab".to_string()));
+        assert_eq!(
+            parse_value("[\"api\", \"raw-git\"]"),
+            toml::Value::Array(vec!["api".into(), "raw-git".into()])
+        );
+    }
+
+    #[test]
+    fn test_set_path_creates_tables() {
+        let mut table = toml::Table::new();
+        let path = parse_key("domains.git.corp.example.max_concurrent").unwrap();
+        set_path(&mut table, &path, toml::Value::Integer(4)).unwrap();
+
+        assert_eq!(
+            table["domains"]["git.corp.example"]["max_concurrent"],
+            toml::Value::Integer(4)
+        );
+
+        let mut table: toml::Table = toml::from_str("limits = 5").unwrap();
+        let path = parse_key("limits.concurrency").unwrap();
+        assert!(set_path(&mut table, &path, toml::Value::Integer(1)).is_err());
+    }
+}
@@ -0,0 +1,120 @@
+use crate::cli::ExplainArgs;
+use crate::util::{get_provider_factory, mask_token, RateLimitInfo, Token};
+use anyhow::Result;
+
+/// Shows how a URL would be fetched: the domain config, the tokens it uses, and
+/// what every strategy returns for it.
+pub async fn explain(args: ExplainArgs) -> Result<()> {
+    let factory = get_provider_factory().await;
+    let provider = factory.get_provider(&args.url).await?;
+    let config = provider.config();
+
+    println!("URL:     {}", args.url);
+    println!(
+        "Domain:  {} ({})",
+        provider.domain,
+        if factory.is_known_domain(&provider.domain) {
+            "known config"
+        } else {
+            "default config"
+        }
+    );
+    println!(
+        "  env var:      {}{}",
+        config.env_var,
+        config
+            .fallback_env_var
+            .as_ref()
+            .map(|fallback| format!(", else {}", fallback))
+            .unwrap_or_default()
+    );
+    println!(
+        "  api pattern:  {}",
+        config.api_pattern.as_deref().unwrap_or("-")
+    );
+    println!(
+        "  forge:        {}",
+        config.forge.map(|f| format!("{:?}", f)).unwrap_or_else(|| "-".to_string())
+    );
+    println!(
+        "  validation:   {}",
+        config.validation_url.as_deref().unwrap_or("-")
+    );
+    println!("  auth:         {}", config.auth);
+    println!("  raw url:      {}", config.raw_url.as_deref().unwrap_or("-"));
+    println!("  strategies:   {}", provider.strategy_names().join(", "));
+    println!();
+
+    let tokens = provider.token_states().await;
+    let mut sources = vec![config.env_var.clone()];
+    sources.extend(config.fallback_env_var.clone());
+    sources.extend(config.tokens.describe());
+    println!("Tokens from {}: {} loaded", sources.join(", "), tokens.len());
+    for token in &tokens {
+        println!("  {}", describe_token(token));
+    }
+    println!();
+
+    println!("Strategies:");
+    let traces = provider.trace_strategies(&args.url).await;
+    for (i, trace) in traces.iter().enumerate() {
+        println!("  {}. {}", i + 1, trace.name);
+        println!(
+            "     url:      {}",
+            trace.readme_url.as_deref().unwrap_or("-")
+        );
+        match trace.status {
+            Some(status) => println!(
+                "     status:   HTTP {} in {}ms",
+                status,
+                trace.elapsed.as_millis()
+            ),
+            None => println!("     status:   - in {}ms", trace.elapsed.as_millis()),
+        }
+        if let Some(rate_limit) = &trace.rate_limit {
+            println!("     limits:   {}", describe_rate_limit(rate_limit));
+        }
+        match &trace.result {
+            Ok(bytes) => println!("     accepted: {} bytes", bytes),
+            Err(e) => println!("     rejected: {}", e),
+        }
+    }
+    println!();
+
+    // readme-get caches the first strategy that builds a URL, without fetching it
+    match traces.iter().find(|t| t.readme_url.is_some()) {
+        Some(trace) => println!("readme-get would use: {}", trace.name),
+        None => println!("readme-get would fail: no strategy builds a README URL"),
+    }
+
+    Ok(())
+}
+
+fn describe_token(token: &Token) -> String {
+    let state = match token.valid {
+        Some(true) => "valid",
+        Some(false) => "invalid",
+        None => "unvalidated",
+    };
+
+    let mut line = format!("{}  {}", mask_token(&token.value), state);
+    if let Some(remaining) = token.remaining {
+        line.push_str(&format!(", {}/{} remaining", remaining, token.limit));
+    }
+    if let Some(reset_at) = token.reset_at {
+        line.push_str(&format!(", resets {}", reset_at.format("%Y-%m-%d %H:%M:%S UTC")));
+    }
+    line
+}
+
+fn describe_rate_limit(rate_limit: &RateLimitInfo) -> String {
+    match rate_limit.reset_at {
+        Some(reset_at) => format!(
+            "{}/{} remaining, resets {}",
+            rate_limit.remaining,
+            rate_limit.limit,
+            reset_at.format("%Y-%m-%d %H:%M:%S UTC")
+        ),
+        None => format!("{}/{} remaining", rate_limit.remaining, rate_limit.limit),
+    }
+}
@@ -0,0 +1,34 @@
+use crate::cli::GetArgs;
+use crate::provider::{FetchError, FetchErrorKind, ProviderTrait};
+use crate::util::get_provider_factory;
+use anyhow::{Context, Result};
+use std::io::Write;
+
+pub async fn get(args: GetArgs) -> Result<()> {
+    let factory = get_provider_factory().await;
+    let provider = factory
+        .get_provider(&args.url)
+        .await
+        .map_err(|e| FetchError::new(FetchErrorKind::InvalidProvider).with_source(e))?;
+
+    let (readme_url, readme) = match args.strategy {
+        Some(kind) => provider.get_readme_with_strategy(&args.url, kind).await?,
+        None => {
+            let readme = provider.get_readme(&args.url).await?;
+            // Resolves to the strategy that just worked, without another request
+            (provider.get_readme_url(&args.url).await?, readme)
+        }
+    };
+
+    if args.print_url {
+        eprintln!("{}", readme_url);
+    }
+
+    match &args.output {
+        Some(path) => std::fs::write(path, readme)
+            .with_context(|| format!("Failed to write README to {}", path.display()))?,
+        None => std::io::stdout().write_all(readme.as_bytes())?,
+    }
+
+    Ok(())
+}
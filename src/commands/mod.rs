@@ -1 +1,9 @@
+pub mod add_org;
+pub mod config;
+pub mod explain;
+pub mod get;
+pub mod plan;
+pub mod read_history;
 pub mod readme_get;
+pub mod retry_failed;
+pub mod status;
@@ -0,0 +1,326 @@
+use crate::commands::readme_get::{is_url_line, readme_path};
+use crate::util::{get_provider_factory, Token};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Assumed round trip of one request, bounding throughput by concurrency alone.
+const ASSUMED_LATENCY_SECS: f64 = 1.0;
+
+/// Quota window assumed once a token resets, when the forge doesn't say otherwise.
+const DEFAULT_WINDOW_SECS: f64 = 3600.0;
+
+/// Combined quota of a domain's valid tokens.
+#[derive(Debug, Clone, PartialEq)]
+struct Quota {
+    remaining: u64,
+    limit: u64,
+    reset_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Estimate {
+    seconds: f64,
+    /// Seconds until the quota runs out, if the run needs more than is left.
+    exhausted_after: Option<f64>,
+}
+
+#[derive(Default)]
+struct DomainPlan {
+    requests: u64,
+    unresolved: u64,
+    strategies: BTreeMap<&'static str, u64>,
+    tokens: Vec<Token>,
+    quota: Option<Quota>,
+    estimate: Option<Estimate>,
+}
+
+/// Prints what `readme-get` would do with the remaining archlist lines: requests per
+/// domain, the strategy each resolves to, token quotas and an estimated duration.
+/// Only token validation URLs are fetched.
+pub(crate) async fn print_plan(
+    lines: &[String],
+    lines_skipped: usize,
+    output_dir: &Path,
+    refresh: bool,
+) -> Result<()> {
+    let factory = get_provider_factory().await;
+    let limits = factory.limits();
+    let mut domains: BTreeMap<String, DomainPlan> = BTreeMap::new();
+    let mut already_downloaded = 0u64;
+    let mut invalid = 0u64;
+
+    for line in lines.iter().filter(|line| is_url_line(line)) {
+        let url = line.trim();
+
+        if !refresh && readme_path(output_dir, url).is_ok_and(|path| path.exists()) {
+            already_downloaded += 1;
+            continue;
+        }
+
+        let provider = match factory.get_provider(url).await {
+            Ok(provider) => provider,
+            Err(e) => {
+                log::debug!("Skipping {} in plan: {}", url, e);
+                invalid += 1;
+                continue;
+            }
+        };
+
+        let plan = domains.entry(provider.domain.clone()).or_default();
+        match provider.resolve_readme_url(url).await {
+            Some((strategy, _)) => {
+                plan.requests += 1;
+                *plan.strategies.entry(strategy).or_insert(0) += 1;
+            }
+            None => plan.unresolved += 1,
+        }
+    }
+
+    let now = Utc::now();
+    for (domain, plan) in domains.iter_mut() {
+        let provider = factory.get_provider(&format!("https://{}/", domain)).await?;
+        plan.tokens = provider.token_states().await;
+        plan.quota = combined_quota(&plan.tokens);
+        plan.estimate = Some(estimate(
+            plan.requests,
+            plan.quota.as_ref(),
+            factory.max_concurrent(domain),
+            limits.velocity,
+            now,
+        ));
+    }
+
+    let total_requests: u64 = domains.values().map(|p| p.requests).sum();
+    let global_floor =
+        total_requests as f64 * ASSUMED_LATENCY_SECS / limits.max_concurrent_global.max(1) as f64;
+    let eta = domains
+        .values()
+        .filter_map(|p| p.estimate.map(|e| e.seconds))
+        .fold(global_floor, f64::max);
+
+    println!(
+        "Plan: {} lines remaining after skipping {}",
+        lines.len(),
+        lines_skipped
+    );
+    println!(
+        "  {} already downloaded, {} invalid urls, {} requests",
+        already_downloaded, invalid, total_requests
+    );
+    println!();
+
+    let width = domains
+        .keys()
+        .map(String::len)
+        .max()
+        .unwrap_or(0)
+        .max("DOMAIN".len());
+    println!(
+        "{:<width$}  {:>8}  {:<24}  {:>6}  {:<20}  {:>10}",
+        "DOMAIN", "REQUESTS", "STRATEGY", "TOKENS", "QUOTA", "ETA",
+        width = width
+    );
+    for (domain, plan) in &domains {
+        let strategies: Vec<String> = plan
+            .strategies
+            .iter()
+            .map(|(name, count)| format!("{}:{}", name, count))
+            .collect();
+        let valid = plan.tokens.iter().filter(|t| t.valid == Some(true)).count();
+        let quota = match &plan.quota {
+            Some(quota) => format!("{}/{}", quota.remaining, quota.limit),
+            None => "unknown".to_string(),
+        };
+        let eta = plan
+            .estimate
+            .map(|e| format_duration(e.seconds))
+            .unwrap_or_default();
+
+        println!(
+            "{:<width$}  {:>8}  {:<24}  {:>6}  {:<20}  {:>10}",
+            domain,
+            plan.requests,
+            strategies.join(" "),
+            format!("{}/{}", valid, plan.tokens.len()),
+            quota,
+            eta,
+            width = width
+        );
+        if plan.unresolved > 0 {
+            println!("{:<width$}  {} urls resolve to no strategy", "", plan.unresolved, width = width);
+        }
+    }
+    println!();
+
+    let mut limited: Vec<(&String, f64, &DomainPlan)> = domains
+        .iter()
+        .filter_map(|(domain, plan)| {
+            plan.estimate
+                .and_then(|e| e.exhausted_after)
+                .map(|after| (domain, after, plan))
+        })
+        .collect();
+    limited.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+    if limited.is_empty() {
+        println!("No domain is expected to run out of quota");
+    } else {
+        println!("Rate limits hit first:");
+        for (domain, after, plan) in limited {
+            let reset = plan
+                .quota
+                .as_ref()
+                .and_then(|q| q.reset_at)
+                .map(|r| format!(", resets {}", r.format("%Y-%m-%d %H:%M:%S UTC")))
+                .unwrap_or_default();
+            println!("  {:<width$}  after {}{}", domain, format_duration(after), reset, width = width);
+        }
+    }
+
+    println!();
+    println!(
+        "Estimated time: {} (assuming {}s per request, {} concurrent per domain, {} global)",
+        format_duration(eta),
+        ASSUMED_LATENCY_SECS,
+        limits.max_concurrent_per_domain,
+        limits.max_concurrent_global
+    );
+
+    Ok(())
+}
+
+/// Sums the quota of valid tokens with known limits. `None` when no token has
+/// reported one, e.g. unauthenticated or unvalidated domains.
+fn combined_quota(tokens: &[Token]) -> Option<Quota> {
+    let valid: Vec<&Token> = tokens
+        .iter()
+        .filter(|t| t.valid == Some(true) && t.remaining.is_some())
+        .collect();
+    if valid.is_empty() {
+        return None;
+    }
+
+    Some(Quota {
+        remaining: valid.iter().filter_map(|t| t.remaining).map(u64::from).sum(),
+        limit: valid.iter().map(|t| u64::from(t.limit)).sum(),
+        reset_at: valid.iter().filter_map(|t| t.reset_at).min(),
+    })
+}
+
+/// Estimates how long `requests` take on one domain. Throughput is bounded by
+/// per-domain concurrency, and with a quota by the smoother, which spreads the
+/// remaining requests over the time to reset at `velocity` times realtime. Past the
+/// quota, the run waits for the reset and continues one window at a time.
+fn estimate(
+    requests: u64,
+    quota: Option<&Quota>,
+    max_concurrent: usize,
+    velocity: f64,
+    now: DateTime<Utc>,
+) -> Estimate {
+    let floor = requests as f64 * ASSUMED_LATENCY_SECS / max_concurrent.max(1) as f64;
+
+    let Some(quota) = quota else {
+        return Estimate {
+            seconds: floor,
+            exhausted_after: None,
+        };
+    };
+
+    let window = quota
+        .reset_at
+        .map(|reset| (reset - now).num_seconds().max(1) as f64)
+        .unwrap_or(DEFAULT_WINDOW_SECS);
+
+    if requests <= quota.remaining {
+        let paced = if quota.remaining == 0 {
+            0.0
+        } else {
+            requests as f64 * window / (quota.remaining as f64 * velocity)
+        };
+        return Estimate {
+            seconds: floor.max(paced),
+            exhausted_after: None,
+        };
+    }
+
+    let exhausted_after = (window / velocity)
+        .min(window)
+        .max(quota.remaining as f64 * ASSUMED_LATENCY_SECS / max_concurrent.max(1) as f64);
+
+    let limit = quota.limit.max(1);
+    let leftover = requests - quota.remaining;
+    let full_windows = (leftover - 1) / limit;
+    let last = leftover - full_windows * limit;
+    let seconds = window
+        + full_windows as f64 * DEFAULT_WINDOW_SECS
+        + last as f64 * DEFAULT_WINDOW_SECS / (limit as f64 * velocity);
+
+    Estimate {
+        seconds: floor.max(seconds),
+        exhausted_after: Some(if quota.remaining == 0 { 0.0 } else { exhausted_after }),
+    }
+}
+
+fn format_duration(seconds: f64) -> String {
+    let total = seconds.round() as u64;
+    let (hours, minutes, secs) = (total / 3600, total % 3600 / 60, total % 60);
+
+    if hours >= 24 {
+        format!("{}d {:02}h", hours / 24, hours % 24)
+    } else if hours > 0 {
+        format!("{}h {:02}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m {:02}s", minutes, secs)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quota(remaining: u64, limit: u64, reset_in: i64, now: DateTime<Utc>) -> Quota {
+        Quota {
+            remaining,
+            limit,
+            reset_at: Some(now + chrono::Duration::seconds(reset_in)),
+        }
+    }
+
+    #[test]
+    fn test_estimate_within_quota_is_paced_by_smoother() {
+        let now = Utc::now();
+        let estimate = estimate(100, Some(&quota(1000, 5000, 1500, now)), 2, 1.5, now);
+        // 1000 remaining over 1500s at 1.5x is one request per second
+        assert_eq!(estimate.seconds, 100.0);
+        assert_eq!(estimate.exhausted_after, None);
+    }
+
+    #[test]
+    fn test_estimate_without_quota_is_bounded_by_concurrency() {
+        let estimate = estimate(100, None, 2, 1.5, Utc::now());
+        assert_eq!(estimate.seconds, 50.0);
+        assert_eq!(estimate.exhausted_after, None);
+    }
+
+    #[test]
+    fn test_estimate_past_quota_waits_for_reset() {
+        let now = Utc::now();
+        let estimate = estimate(6000, Some(&quota(1000, 5000, 1500, now)), 2, 1.5, now);
+        assert_eq!(estimate.exhausted_after, Some(1000.0));
+        // 1500s to reset, then 5000 requests over one window at 1.5x
+        assert_eq!(estimate.seconds, 1500.0 + 3600.0 / 1.5);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(42.0), "42s");
+        assert_eq!(format_duration(125.0), "2m 05s");
+        assert_eq!(format_duration(3.0 * 3600.0 + 60.0), "3h 01m");
+        assert_eq!(format_duration(50.0 * 3600.0), "2d 02h");
+    }
+}
@@ -0,0 +1,59 @@
+use crate::archlist::{self, Placement, ARCHLIST_FILE};
+use crate::cli::ReadHistoryArgs;
+use crate::history::{self, looks_like_forge, rank_repos};
+use crate::logging::progress;
+use crate::util::get_provider_factory;
+use crate::config::ConfigManager;
+use anyhow::Result;
+use std::path::PathBuf;
+
+pub async fn read_history(args: ReadHistoryArgs) -> Result<()> {
+    let archlist_path = ConfigManager::new()?
+        .find_archlist(args.archlist.archlist.as_deref())
+        .unwrap_or_else(|| PathBuf::from(ARCHLIST_FILE));
+    let factory = get_provider_factory().await;
+
+    let mut entries = Vec::new();
+    for profile in &args.profiles {
+        entries.extend(history::read_history(profile)?);
+    }
+
+    let existing = archlist::read_entries(&archlist_path)?;
+    let mut candidates: Vec<_> = rank_repos(&entries, |host| {
+        factory.is_known_domain(host) || looks_like_forge(host)
+    })
+    .into_iter()
+    .filter(|repo| repo.visit_count >= args.min_visits)
+    .filter(|repo| !existing.contains(&archlist::normalize_url(&repo.url)))
+    .collect();
+
+    if let Some(limit) = args.limit {
+        candidates.truncate(limit);
+    }
+
+    if args.add || args.append {
+        let placement = if args.append {
+            Placement::Bottom
+        } else {
+            Placement::Top
+        };
+
+        let urls: Vec<String> = candidates.into_iter().map(|repo| repo.url).collect();
+        let added = archlist::add_urls(&archlist_path, &urls, placement)?;
+        for url in &added {
+            progress!("Added {}", url);
+        }
+        progress!("Added {} repositories from browser history", added.len());
+        return Ok(());
+    }
+
+    for repo in &candidates {
+        let last_visit = repo
+            .last_visit
+            .map(|t| t.format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|| "-".to_string());
+        println!("{}\t{}\t{}", repo.url, repo.visit_count, last_visit);
+    }
+
+    Ok(())
+}
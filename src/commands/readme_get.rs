@@ -1,31 +1,91 @@
-use crate::cli::ReadmeGetArgs;
-use crate::config::ConfigManager;
-use crate::failure::log_failure;
-use crate::provider::ProviderTrait;
-use crate::util::{get_provider_factory, ReverseBufferReader};
-use anyhow::Result;
+use crate::checkpoint::{file_stamp, ArchlistFingerprint, Checkpoint, LineHasher};
+use crate::cli::{OutputFormat, ReadmeGetArgs};
+use crate::commands::plan::print_plan;
+use crate::config::{ConfigManager, RunState};
+use crate::events::{EventKind, EventSink};
+use crate::failure::{export_fail_file, FailureLedger};
+use crate::logging::progress;
+use crate::progress::ProgressDisplay;
+use crate::provider::{FetchError, FetchErrorKind};
+use crate::shutdown::Shutdown;
+use crate::stats::{Outcome, RunStats, RunStatus, RunSummary};
+use crate::util::{get_provider_factory, ForwardBufferReader, LineReader, ReverseBufferReader};
+use anyhow::{Context, Result};
+use futures::future;
 use futures::stream::{self, StreamExt};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-pub async fn readme_get(_args: ReadmeGetArgs) -> Result<()> {
-    let archlist_path = "archlist";
-    let fail_file = ".fail";
+const README_FILE: &str = "README.md";
 
-    let config_manager = ConfigManager::new()?;
-    let config = config_manager.load()?;
-    let config = Arc::new(Mutex::new(config));
-    let lines_to_skip = config.lock().await.lines_from_bottom;
+pub async fn readme_get(args: ReadmeGetArgs) -> Result<RunStatus> {
+    let config_manager = Arc::new(ConfigManager::new()?);
 
-    let mut reader = ReverseBufferReader::new(archlist_path)?;
+    let archlist_path = config_manager
+        .find_archlist(args.archlist.archlist.as_deref())
+        .with_context(|| {
+            let searched: Vec<String> = config_manager
+                .archlist_candidates()
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect();
+            format!("No archlist found, searched: {}", searched.join(", "))
+        })?;
+    let output_dir = args.output.output_dir.as_path();
+    let ledger_path = args.output.ledger_path();
 
-    let lines_read = Arc::new(Mutex::new(0usize));
+    // A plan is a dry run: it must not claim legacy state or create the ledger
+    let state = if args.plan {
+        config_manager.peek_state(&archlist_path)?
+    } else {
+        config_manager.load_state(&archlist_path)?
+    };
+    let top_down = args.top_down;
+    let refresh = args.refresh;
+
+    let (saved_lines, saved_fingerprint) = if top_down {
+        (state.lines_from_top, state.top_fingerprint.clone())
+    } else {
+        (state.lines_from_bottom, state.bottom_fingerprint.clone())
+    };
+    let state = Arc::new(Mutex::new(state));
+
+    let stamp = file_stamp(&archlist_path)?;
+    let (mut reader, lines_skipped, hasher) = open_at_position(
+        &archlist_path,
+        top_down,
+        saved_lines,
+        saved_fingerprint.as_ref(),
+        stamp,
+    )?;
+
+    let mut lines = Vec::new();
+    while let Some(line) = reader.read_line()? {
+        lines.push(line);
+    }
+
+    if args.plan {
+        print_plan(&lines, lines_skipped, output_dir, refresh).await?;
+        return Ok(RunStatus::Success);
+    }
+
+    let ledger = FailureLedger::open(&ledger_path)?;
+    let ledger = &ledger;
+
+    let lines: Arc<[String]> = lines.into();
+    let checkpoint = Arc::new(Mutex::new(Checkpoint::new(
+        lines_skipped,
+        hasher,
+        Arc::clone(&lines),
+    )));
     let should_stop = Arc::new(Mutex::new(false));
 
-    let config_clone = Arc::clone(&config);
-    let lines_read_clone = Arc::clone(&lines_read);
+    let config_manager_clone = Arc::clone(&config_manager);
+    let archlist_clone = archlist_path.clone();
+    let state_clone = Arc::clone(&state);
+    let checkpoint_clone = Arc::clone(&checkpoint);
     let should_stop_clone = Arc::clone(&should_stop);
 
     tokio::spawn(async move {
@@ -36,111 +96,372 @@ pub async fn readme_get(_args: ReadmeGetArgs) -> Result<()> {
                 break;
             }
 
-            let current_lines = *lines_read_clone.lock().await;
-            let mut cfg = config_clone.lock().await;
-            cfg.lines_from_bottom = current_lines;
-
-            if let Err(e) = config_manager.save(&cfg) {
-                eprintln!("Failed to save config: {}", e);
-            }
+            save_position(
+                &config_manager_clone,
+                &archlist_clone,
+                &state_clone,
+                &checkpoint_clone,
+                top_down,
+                stamp,
+            )
+            .await;
         }
     });
 
-    for _ in 0..lines_to_skip {
-        if reader.read_line()?.is_none() {
-            break;
+    let url_count = lines.iter().filter(|line| is_url_line(line)).count();
+    let stats = Arc::new(
+        RunStats::new(url_count, args.max_failures)
+            .with_urls(lines.iter().filter(|line| is_url_line(line)).map(|line| line.trim())),
+    );
+    let events = EventSink::new(args.format == OutputFormat::Jsonl);
+    let display = start_display(args.progress, &events, &stats);
+
+    let ctx = UrlContext {
+        output_dir,
+        ledger,
+        refresh,
+        stats: &stats,
+        events: &events,
+    };
+    let ctx = &ctx;
+
+    let concurrency = get_provider_factory().await.limits().concurrency.max(1);
+    let shutdown = Shutdown::listen(Arc::clone(&stats));
+    let run = stream::iter(lines.iter().enumerate())
+        .take_while(|_| future::ready(!stats.is_aborted()))
+        .map(|(index, line)| {
+            let checkpoint = Arc::clone(&checkpoint);
+            async move {
+                if is_url_line(line) {
+                    process_url(line.trim(), ctx).await;
+                }
+
+                checkpoint.lock().await.complete(index);
+            }
+        })
+        .buffer_unordered(concurrency)
+        .collect::<Vec<_>>();
+    shutdown.drain(run).await;
+
+    if let Some(display) = display {
+        display.finish();
+    }
+
+    *should_stop.lock().await = true;
+    save_position(
+        &config_manager,
+        &archlist_path,
+        &state,
+        &checkpoint,
+        top_down,
+        stamp,
+    )
+    .await;
+
+    if let Some(fail_file) = &args.fail_file {
+        let count = export_fail_file(&ledger_path, fail_file)?;
+        progress!("Exported {} failures to {}", count, fail_file.display());
+    }
+
+    Ok(finish_run(&stats, &events).await)
+}
+
+/// What `process_url` needs from the run it is part of.
+pub(crate) struct UrlContext<'a> {
+    pub output_dir: &'a Path,
+    pub ledger: &'a FailureLedger,
+    pub refresh: bool,
+    pub stats: &'a RunStats,
+    pub events: &'a EventSink,
+}
+
+pub(crate) async fn process_url(url: &str, ctx: &UrlContext<'_>) {
+    ctx.events.url_event(url, EventKind::Started);
+
+    let output_path = match readme_path(ctx.output_dir, url) {
+        Ok(path) => path,
+        Err(e) => {
+            log::error!("Failed to create path for {}: {}", url, e);
+            report_failure(url, ctx, &FetchError::new(FetchErrorKind::Unknown).with_source(e));
+            return;
         }
+    };
+
+    if !ctx.refresh && output_path.exists() {
+        log::debug!("Skipping {}: README already downloaded", url);
+        ctx.stats.record(url, None, Outcome::Skipped);
+        ctx.events.url_event(
+            url,
+            EventKind::Skipped {
+                reason: "already-downloaded",
+            },
+        );
+        return;
     }
 
-    let mut urls = Vec::new();
-    while let Some(line) = reader.read_line()? {
-        urls.push(line);
+    if ctx.stats.is_exhausted(url) {
+        let error = FetchError::new(FetchErrorKind::NoTokens)
+            .with_source(anyhow::anyhow!("skipped, the domain ran out of tokens earlier in the run"));
+        log::debug!("Skipping {}: {}", url, error);
+        record_failure(url, ctx, &error);
+        return;
     }
 
-    stream::iter(urls)
-        .filter_map(|url| async move {
-            let url = url.trim();
-            if url.is_empty() || url.starts_with('#') {
-                None
-            } else {
-                Some(url.to_string())
-            }
-        })
-        .map(|url| {
-            let lines_read_clone = Arc::clone(&lines_read);
-            async move {
-                let url = url.trim();
-                let fail_file_owned = fail_file.to_string();
-                let url_owned = url.to_string();
-
-                let factory = get_provider_factory().await;
-
-            let provider = match factory.get_provider(&url_owned).await {
-                Ok(provider) => provider,
-                Err(e) => {
-                    let _ = log_failure(&url_owned, "INVALID-PROVIDER", &fail_file_owned);
-                    eprintln!("Failed to get provider for {}: {}", url_owned, e);
-                    *lines_read_clone.lock().await += 1;
-                    return;
-                }
-            };
-
-            match provider.get_readme(&url_owned).await {
-                Ok(readme) => {
-                    let output_path = match url_to_path(&url_owned) {
-                        Ok(path) => path,
-                        Err(e) => {
-                            eprintln!("Failed to create path for {}: {}", url_owned, e);
-                            *lines_read_clone.lock().await += 1;
-                            return;
-                        }
-                    };
-
-                    if let Some(parent) = Path::new(&output_path).parent() {
-                        if let Err(e) = fs::create_dir_all(parent) {
-                            eprintln!("Failed to create directory {}: {}", parent.display(), e);
-                        }
-                    }
-
-                    if let Err(e) = fs::write(&output_path, readme) {
-                        eprintln!("Failed to write README to {}: {}", output_path, e);
-                    } else {
-                        println!("Downloaded README from {}", url_owned);
-                    }
+    let factory = get_provider_factory().await;
+
+    let provider = match factory.get_provider(url).await {
+        Ok(provider) => provider,
+        Err(e) => {
+            let error = FetchError::new(FetchErrorKind::InvalidProvider).with_source(e);
+            log::warn!("Failed to get provider for {}: {}", url, error);
+            record_failure(url, ctx, &error);
+            return;
+        }
+    };
+
+    let resolved = match provider.resolve_readme(url).await {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            let error = FetchError::from(e);
+            log::warn!("Failed to fetch README from {}: {}", url, error);
+            record_failure(url, ctx, &error);
+            return;
+        }
+    };
+    ctx.events.url_event(
+        url,
+        EventKind::StrategyResolved {
+            strategy: resolved.strategy,
+            readme_url: resolved.readme_url.clone(),
+        },
+    );
+
+    ctx.stats.fetch_started(&provider.domain);
+    let result = provider.fetch_readme(url, &resolved).await;
+    ctx.stats.fetch_finished(&provider.domain);
+
+    let rate_limit = match &result {
+        Ok(fetched) => fetched.rate_limit.as_ref().filter(|info| info.remaining == 0),
+        Err(e) => e.rate_limit.as_ref(),
+    };
+    let limited = matches!(
+        &result,
+        Err(e) if matches!(e.kind, FetchErrorKind::RateLimited | FetchErrorKind::NoTokens)
+    );
+    if limited || rate_limit.is_some_and(|info| info.remaining == 0) {
+        ctx.events.url_event(
+            url,
+            EventKind::RateLimited {
+                remaining: rate_limit.map(|info| info.remaining),
+                limit: rate_limit.map(|info| info.limit),
+                reset_at: rate_limit.and_then(|info| info.reset_at),
+            },
+        );
+    }
+
+    match result {
+        Ok(fetched) => {
+            if let Some(parent) = output_path.parent() {
+                if let Err(e) = fs::create_dir_all(parent) {
+                    log::error!("Failed to create directory {}: {}", parent.display(), e);
                 }
-                Err(e) => {
-                    let error_code = if e.to_string().contains("404") {
-                        "NO-README"
-                    } else if e.to_string().contains("Not Found") {
-                        "NO-REPO"
-                    } else if e.to_string().contains("rate limit") || e.to_string().contains("403") || e.to_string().contains("429") {
-                        "RATE-LIMIT"
-                    } else if e.to_string().contains("No valid tokens available") {
-                        "NO-TOKENS"
-                    } else {
-                        "UNKNOWN"
-                    };
-
-                    let _ = log_failure(&url_owned, error_code, &fail_file_owned);
-                    eprintln!("Failed to fetch README from {}: {}", url_owned, e);
+            }
+
+            if let Err(e) = fs::write(&output_path, &fetched.content) {
+                log::error!("Failed to write README to {}: {}", output_path.display(), e);
+                report_failure(
+                    url,
+                    ctx,
+                    &FetchError::new(FetchErrorKind::Unknown).with_source(e.into()),
+                );
+            } else {
+                progress!("Downloaded README from {}", url);
+                if let Err(e) = ctx.ledger.record_success(url) {
+                    log::error!("Failed to record success for {}: {}", url, e);
                 }
+
+                let bytes = fetched.content.len() as u64;
+                ctx.stats.record(url, Some(resolved.strategy), Outcome::Downloaded { bytes });
+                ctx.events.url_event(
+                    url,
+                    EventKind::Fetched {
+                        bytes,
+                        status: fetched.status,
+                        path: output_path.display().to_string(),
+                    },
+                );
             }
+        }
+        Err(e) => {
+            log::warn!("Failed to fetch README from {}: {}", url, e);
+            record_failure(url, ctx, &e);
+        }
+    }
+}
+
+/// Writes a fetch failure to the ledger, then reports it.
+fn record_failure(url: &str, ctx: &UrlContext<'_>, error: &FetchError) {
+    if let Err(e) = ctx.ledger.record_failure(url, error) {
+        log::error!("Failed to record failure for {}: {}", url, e);
+    }
+    report_failure(url, ctx, error);
+}
+
+/// Counts a failure and emits its event. Local failures, like an unwritable output
+/// directory, are reported without a ledger entry.
+fn report_failure(url: &str, ctx: &UrlContext<'_>, error: &FetchError) {
+    ctx.stats.record(url, error.strategy, Outcome::Failed(error.code()));
+    ctx.events.url_event(
+        url,
+        EventKind::Failed {
+            code: error.code(),
+            status: error.status,
+            strategy: error.strategy,
+            message: error.to_string(),
+        },
+    );
+}
 
-            *lines_read_clone.lock().await += 1;
+/// Starts the live display if asked for, unless stdout carries the event stream.
+pub(crate) fn start_display(
+    progress: bool,
+    events: &EventSink,
+    stats: &Arc<RunStats>,
+) -> Option<ProgressDisplay> {
+    if progress && !events.is_enabled() {
+        ProgressDisplay::start(Arc::clone(stats))
+    } else {
+        None
+    }
+}
+
+/// Reports the end of a run: the summary as text or as the final event, and the
+/// status the process exits with.
+pub(crate) async fn finish_run(stats: &RunStats, events: &EventSink) -> RunStatus {
+    let tokens = get_provider_factory().await.tokens_by_domain().await;
+    let summary = RunSummary::new(stats.snapshot(), &tokens);
+
+    if let Some(reason) = summary.aborted {
+        log::warn!("Run aborted early: {:?}", reason);
+    }
+
+    if events.is_enabled() {
+        events.summary(&summary);
+    } else {
+        for line in summary.lines() {
+            progress!("{}", line);
         }
-        })
-        .buffer_unordered(10)
-        .collect::<Vec<_>>()
-        .await;
+    }
 
-    *should_stop.lock().await = true;
+    summary.status
+}
+
+/// Archlist lines that hold a URL, as opposed to blank lines and `#` comments.
+pub(crate) fn is_url_line(line: &str) -> bool {
+    let line = line.trim();
+    !line.is_empty() && !line.starts_with('#')
+}
+
+pub(crate) fn open_reader(path: &Path, top_down: bool) -> Result<Box<dyn LineReader>> {
+    Ok(if top_down {
+        Box::new(ForwardBufferReader::new(path)?)
+    } else {
+        Box::new(ReverseBufferReader::new(path)?)
+    })
+}
+
+/// Opens the archlist in processing order and skips the lines a previous run
+/// completed. If those lines changed since the position was saved, starts over
+/// rather than resuming at a line that no longer means the same thing.
+fn open_at_position(
+    path: &Path,
+    top_down: bool,
+    saved_lines: usize,
+    fingerprint: Option<&ArchlistFingerprint>,
+    stamp: (u64, Option<i64>),
+) -> Result<(Box<dyn LineReader>, usize, LineHasher)> {
+    let mut reader = open_reader(path, top_down)?;
+    let mut hasher = LineHasher::new();
+
+    for _ in 0..saved_lines {
+        match reader.read_line()? {
+            Some(line) => hasher.add_line(&line),
+            None => break,
+        }
+    }
+
+    if let Some(fingerprint) = fingerprint {
+        if fingerprint.consumed_hash != hasher.hex() {
+            log::warn!(
+                "Archlist {} changed in lines processed by the last run, starting over",
+                path.display()
+            );
+            return Ok((open_reader(path, top_down)?, 0, LineHasher::new()));
+        }
+
+        if (fingerprint.size, fingerprint.modified) != stamp {
+            log::info!(
+                "Archlist {} modified since the last run, processed lines unchanged",
+                path.display()
+            );
+        }
+    }
+
+    if reader.lines_read() < saved_lines {
+        log::warn!(
+            "Archlist has {} lines, fewer than the saved position of {}",
+            reader.lines_read(),
+            saved_lines
+        );
+    }
+
+    let lines_skipped = reader.lines_read();
+    Ok((reader, lines_skipped, hasher))
+}
+
+/// Saves the contiguous completed position and the fingerprint of those lines.
+async fn save_position(
+    config_manager: &ConfigManager,
+    archlist: &Path,
+    state: &Mutex<RunState>,
+    checkpoint: &Mutex<Checkpoint>,
+    top_down: bool,
+    stamp: (u64, Option<i64>),
+) {
+    let (position, consumed_hash) = {
+        let checkpoint = checkpoint.lock().await;
+        (checkpoint.position(), checkpoint.consumed_hash())
+    };
+
+    let fingerprint = ArchlistFingerprint {
+        size: stamp.0,
+        modified: stamp.1,
+        consumed_hash,
+    };
+
+    let mut state = state.lock().await;
+    if top_down {
+        state.lines_from_top = position;
+        state.top_fingerprint = Some(fingerprint);
+    } else {
+        state.lines_from_bottom = position;
+        state.bottom_fingerprint = Some(fingerprint);
+    }
+
+    if let Err(e) = config_manager.save_state(archlist, &state) {
+        log::error!("Failed to save position: {}", e);
+    }
+}
 
-    Ok(())
+/// Where a repository's README is written under the output directory.
+pub(crate) fn readme_path(output_dir: &Path, url: &str) -> Result<PathBuf> {
+    Ok(output_dir.join(url_to_path(url)?).join(README_FILE))
 }
 
-fn url_to_path(url: &str) -> Result<String> {
+/// Directory a repository's README is written into, relative to the output directory.
+fn url_to_path(url: &str) -> Result<PathBuf> {
     let url = url
         .trim_start_matches("http://")
         .trim_start_matches("https://");
-    Ok(url.to_string())
+    Ok(PathBuf::from(url))
 }
@@ -0,0 +1,80 @@
+use crate::cli::{OutputFormat, RetryFailedArgs};
+use crate::commands::readme_get::{finish_run, process_url, start_display, UrlContext};
+use crate::events::EventSink;
+use crate::failure::{current_failures, FailureLedger};
+use crate::logging::progress;
+use crate::shutdown::Shutdown;
+use crate::stats::{RunStats, RunStatus};
+use crate::util::get_provider_factory;
+use anyhow::Result;
+use chrono::Utc;
+use futures::future;
+use futures::stream::{self, StreamExt};
+use std::sync::Arc;
+
+pub async fn retry_failed(args: RetryFailedArgs) -> Result<RunStatus> {
+    let output_dir = args.output.output_dir.as_path();
+    let ledger_path = args.output.ledger_path();
+    let events = EventSink::new(args.format == OutputFormat::Jsonl);
+
+    let codes: Vec<String> = args.code.iter().map(|c| c.trim().to_uppercase()).collect();
+    let cutoff = args
+        .older_than
+        .map(|age| {
+            Utc::now()
+                .checked_sub_signed(age)
+                .ok_or_else(|| anyhow::anyhow!("--older-than age too large"))
+        })
+        .transpose()?;
+
+    let urls: Vec<String> = current_failures(&ledger_path)?
+        .into_iter()
+        .filter(|entry| codes.is_empty() || codes.contains(&entry.code))
+        .filter(|entry| cutoff.is_none_or(|cutoff| entry.timestamp <= cutoff))
+        .map(|entry| entry.url)
+        .collect();
+
+    if urls.is_empty() {
+        progress!("No failures to retry in {}", ledger_path.display());
+        return Ok(finish_run(&RunStats::new(0, None), &events).await);
+    }
+
+    progress!("Retrying {} failed URLs", urls.len());
+
+    let ledger = FailureLedger::open(&ledger_path)?;
+    let ledger = &ledger;
+
+    let stats = Arc::new(
+        RunStats::new(urls.len(), args.max_failures).with_urls(urls.iter().map(String::as_str)),
+    );
+    let display = start_display(args.progress, &events, &stats);
+
+    let ctx = UrlContext {
+        output_dir,
+        ledger,
+        refresh: true,
+        stats: &stats,
+        events: &events,
+    };
+    let ctx = &ctx;
+
+    let concurrency = get_provider_factory().await.limits().concurrency.max(1);
+    let shutdown = Shutdown::listen(Arc::clone(&stats));
+    let run = stream::iter(urls)
+        .take_while(|_| future::ready(!stats.is_aborted()))
+        .map(|url| async move {
+            process_url(&url, ctx).await;
+        })
+        .buffer_unordered(concurrency)
+        .collect::<Vec<_>>();
+    shutdown.drain(run).await;
+
+    if let Some(display) = display {
+        display.finish();
+    }
+
+    let remaining = current_failures(&ledger_path)?.len();
+    progress!("{} failures remaining", remaining);
+
+    Ok(finish_run(&stats, &events).await)
+}
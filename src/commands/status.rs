@@ -0,0 +1,160 @@
+use crate::cli::StatusArgs;
+use crate::commands::readme_get::{open_reader, readme_path};
+use crate::config::ConfigManager;
+use crate::failure::read_latest;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Default, Serialize)]
+struct Counts {
+    total: usize,
+    downloaded: usize,
+    failed: usize,
+    pending: usize,
+    never_attempted: usize,
+    failed_by_code: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct StatusReport {
+    archlist: String,
+    direction: &'static str,
+    position: usize,
+    totals: Counts,
+    domains: BTreeMap<String, Counts>,
+}
+
+enum Outcome<'a> {
+    Downloaded,
+    Failed(&'a str),
+    Pending,
+    NeverAttempted,
+}
+
+impl Counts {
+    fn add(&mut self, outcome: &Outcome) {
+        self.total += 1;
+        match outcome {
+            Outcome::Downloaded => self.downloaded += 1,
+            Outcome::Failed(code) => {
+                self.failed += 1;
+                *self.failed_by_code.entry(code.to_string()).or_insert(0) += 1;
+            }
+            Outcome::Pending => self.pending += 1,
+            Outcome::NeverAttempted => self.never_attempted += 1,
+        }
+    }
+}
+
+/// Classifies every archlist URL: README on disk, latest ledger entry a failure,
+/// passed by the saved position with neither (pending), or not yet reached.
+pub fn status(args: StatusArgs) -> Result<()> {
+    let config_manager = ConfigManager::new()?;
+    let archlist_path = config_manager
+        .find_archlist(args.archlist.archlist.as_deref())
+        .context("No archlist found")?;
+    let output_dir = args.output.output_dir.as_path();
+
+    let state = config_manager.peek_state(&archlist_path)?;
+    let position = if args.top_down {
+        state.lines_from_top
+    } else {
+        state.lines_from_bottom
+    };
+
+    let latest = read_latest(&args.output.ledger_path())?;
+
+    let mut report = StatusReport {
+        archlist: archlist_path.display().to_string(),
+        direction: if args.top_down { "top-down" } else { "bottom-up" },
+        position,
+        totals: Counts::default(),
+        domains: BTreeMap::new(),
+    };
+
+    let mut reader = open_reader(&archlist_path, args.top_down)?;
+    let mut index = 0;
+    while let Some(line) = reader.read_line()? {
+        let passed = index < position;
+        index += 1;
+
+        let url = line.trim();
+        if url.is_empty() || url.starts_with('#') {
+            continue;
+        }
+
+        let downloaded = readme_path(output_dir, url)
+            .map(|path| path.exists())
+            .unwrap_or(false);
+        let failure = latest.get(url).filter(|entry| !entry.is_success());
+
+        let outcome = match failure {
+            _ if downloaded => Outcome::Downloaded,
+            Some(entry) => Outcome::Failed(&entry.code),
+            None if passed => Outcome::Pending,
+            None => Outcome::NeverAttempted,
+        };
+
+        let domain = url::Url::parse(url)
+            .ok()
+            .and_then(|parsed| parsed.host_str().map(str::to_string))
+            .unwrap_or_else(|| "(invalid)".to_string());
+
+        report.totals.add(&outcome);
+        report.domains.entry(domain).or_default().add(&outcome);
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    print_report(&report);
+    Ok(())
+}
+
+fn print_report(report: &StatusReport) {
+    println!("Archlist: {}", report.archlist);
+    println!("Position: {} lines ({})", report.position, report.direction);
+    println!();
+
+    let width = report
+        .domains
+        .keys()
+        .map(String::len)
+        .max()
+        .unwrap_or(0)
+        .max("DOMAIN".len());
+
+    println!(
+        "{:<width$}  {:>8}  {:>10}  {:>8}  {:>8}  {:>15}",
+        "DOMAIN", "TOTAL", "DOWNLOADED", "FAILED", "PENDING", "NEVER-ATTEMPTED",
+        width = width
+    );
+    for (domain, counts) in &report.domains {
+        print_row(domain, counts, width);
+    }
+    print_row("TOTAL", &report.totals, width);
+
+    if !report.totals.failed_by_code.is_empty() {
+        println!();
+        println!("Failures by code:");
+        for (code, count) in &report.totals.failed_by_code {
+            println!("  {:<16} {}", code, count);
+        }
+    }
+}
+
+fn print_row(domain: &str, counts: &Counts, width: usize) {
+    println!(
+        "{:<width$}  {:>8}  {:>10}  {:>8}  {:>8}  {:>15}",
+        domain,
+        counts.total,
+        counts.downloaded,
+        counts.failed,
+        counts.pending,
+        counts.never_attempted,
+        width = width
+    );
+}
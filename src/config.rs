@@ -1,16 +1,192 @@
-use anyhow::{Context, Result};
-use config::{Config, ConfigError, File};
+use crate::archlist::ARCHLIST_FILE;
+use crate::checkpoint::{ArchlistFingerprint, LineHasher};
+use crate::provider::domain::{AuthScheme, ForgeKind};
+use crate::provider::strategy::StrategyKind;
+use anyhow::{bail, Context, Result};
+use config::{Config, ConfigError, Environment, File};
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+/// Schema version of the config file. Files without one are version 1, which also
+/// held the run state.
+pub const CONFIG_VERSION: u32 = 2;
+
+/// Project-local settings, looked up in the working directory and its parents.
+pub const PROJECT_CONFIG_FILE: &str = ".archive-list.toml";
+
+/// Prefix of environment overrides, e.g. `ARCHIVE_LIST_LIMITS__CONCURRENCY=20`.
+pub const ENV_PREFIX: &str = "ARCHIVE_LIST";
+
+/// Settings, layered as defaults < user config < project `.archive-list.toml` <
+/// `ARCHIVE_LIST_*` env vars. CLI flags are applied on top by the caller.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppConfig {
+    pub version: u32,
+    pub limits: LimitsConfig,
+    /// Per-domain settings, keyed by host, e.g. `[domains."github.com"]`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub domains: BTreeMap<String, DomainSettings>,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            limits: LimitsConfig::default(),
+            domains: BTreeMap::new(),
+        }
+    }
+}
+
+/// Where runs left off in one archlist, kept in a state file of its own.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunState {
+    /// Canonical path of the archlist, for reference; the file name is keyed by it.
+    pub archlist: Option<PathBuf>,
     pub lines_from_bottom: usize,
+    pub lines_from_top: usize,
+    pub bottom_fingerprint: Option<ArchlistFingerprint>,
+    pub top_fingerprint: Option<ArchlistFingerprint>,
+}
+
+/// Concurrency, pacing and timeouts for every request. The `[limits]` table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    /// URLs processed at once by `readme-get` and `retry-failed`.
+    pub concurrency: usize,
+    pub max_concurrent_global: usize,
+    pub max_concurrent_per_domain: usize,
+    pub micro_interval_secs: u64,
+    pub velocity: f64,
+    /// Whole-request timeout, 0 for none.
+    pub request_timeout_secs: u64,
+    /// Connection timeout, 0 for none.
+    pub connect_timeout_secs: u64,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: 10,
+            max_concurrent_global: 10,
+            max_concurrent_per_domain: 2,
+            micro_interval_secs: 2,
+            velocity: 1.5,
+            request_timeout_secs: 60,
+            connect_timeout_secs: 10,
+        }
+    }
+}
+
+impl LimitsConfig {
+    pub fn request_timeout(&self) -> Option<Duration> {
+        (self.request_timeout_secs > 0).then(|| Duration::from_secs(self.request_timeout_secs))
+    }
+
+    pub fn connect_timeout(&self) -> Option<Duration> {
+        (self.connect_timeout_secs > 0).then(|| Duration::from_secs(self.connect_timeout_secs))
+    }
+}
+
+/// Fields of a `[domains."host"]` table.
+pub const DOMAIN_KEYS: [&str; 12] = [
+    "forge",
+    "env_var",
+    "token_file",
+    "token_command",
+    "keyring",
+    "git_credential",
+    "api_pattern",
+    "validation_url",
+    "auth",
+    "strategies",
+    "raw_url",
+    "max_concurrent",
+];
+
+/// Settings for one domain, merged over its built-in config if it has one. See
+/// `DomainConfig` for what each field does.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DomainSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forge: Option<ForgeKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env_var: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_file: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_command: Option<String>,
+    /// Look tokens up in the Secret Service keyring.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keyring: Option<bool>,
+    /// Ask `git credential fill` for a token, falling back to `~/.netrc`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_credential: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_pattern: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validation_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth: Option<AuthScheme>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strategies: Option<Vec<StrategyKind>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw_url: Option<String>,
+    /// Concurrent requests to this domain, instead of `max_concurrent_per_domain`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_concurrent: Option<usize>,
+}
+
+/// What the HTTP client and providers are built from: the global limits and the
+/// per-domain overrides.
+#[derive(Debug, Clone, Default)]
+pub struct ProviderSettings {
+    pub limits: LimitsConfig,
+    pub domains: BTreeMap<String, DomainSettings>,
+}
+
+impl ProviderSettings {
+    /// Concurrent requests allowed to `domain`.
+    pub fn max_concurrent(&self, domain: &str) -> usize {
+        self.domains
+            .get(domain)
+            .and_then(|settings| settings.max_concurrent)
+            .unwrap_or(self.limits.max_concurrent_per_domain)
+            .max(1)
+    }
+
+    /// The highest per-domain concurrency any domain is allowed, which the shared
+    /// client's limiter is built with so that overrides can raise it.
+    pub fn max_concurrent_any_domain(&self) -> usize {
+        self.domains
+            .values()
+            .filter_map(|settings| settings.max_concurrent)
+            .fold(self.limits.max_concurrent_per_domain, usize::max)
+            .max(1)
+    }
+}
+
+impl AppConfig {
+    pub fn provider_settings(&self) -> ProviderSettings {
+        ProviderSettings {
+            limits: self.limits.clone(),
+            domains: self.domains.clone(),
+        }
+    }
 }
 
 pub struct ConfigManager {
     config_path: PathBuf,
+    state_dir: PathBuf,
+    proj_dirs: ProjectDirs,
 }
 
 impl ConfigManager {
@@ -22,25 +198,481 @@ impl ConfigManager {
         std::fs::create_dir_all(config_dir)?;
 
         let config_path = config_dir.join("config.toml");
+        let state_dir = proj_dirs.data_dir().join("state");
+
+        Ok(Self {
+            config_path,
+            state_dir,
+            proj_dirs,
+        })
+    }
+
+    /// The nearest `.archive-list.toml` in the working directory or its parents.
+    pub fn project_config_path(&self) -> Option<PathBuf> {
+        let cwd = std::env::current_dir().ok()?;
+        cwd.ancestors()
+            .map(|dir| dir.join(PROJECT_CONFIG_FILE))
+            .find(|path| path.is_file())
+    }
 
-        Ok(Self { config_path })
+    /// The user config file.
+    pub fn config_path(&self) -> &Path {
+        &self.config_path
     }
 
+    /// Loads the layered settings, migrating the user config file first if it
+    /// predates the current schema.
     pub fn load(&self) -> Result<AppConfig> {
-        if !self.config_path.exists() {
-            return Ok(AppConfig::default());
-        }
+        self.migrate()?;
 
-        let config = Config::builder()
-            .add_source(File::from(self.config_path.as_path()))
-            .build()?;
+        let mut builder = Config::builder();
+        for path in [Some(self.config_path.clone()), self.project_config_path()]
+            .into_iter()
+            .flatten()
+            .filter(|path| path.exists())
+        {
+            check_version(&path)?;
+            builder = builder.add_source(File::from(path.as_path()));
+        }
+        let config = builder.add_source(env_source()).build()?;
 
         config.try_deserialize().map_err(|e: ConfigError| e.into())
     }
 
-    pub fn save(&self, config: &AppConfig) -> Result<()> {
-        let toml = toml::to_string_pretty(config)?;
-        std::fs::write(&self.config_path, toml)?;
-        Ok(())
+    /// Reads one config file as a TOML table.
+    pub fn read_file(&self, path: &Path) -> Result<toml::Table> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Checks `settings` against the `AppConfig` schema and writes them to `path`,
+    /// the user config file or a project one, at the current schema version.
+    pub fn save(&self, path: &Path, settings: &toml::Table) -> Result<()> {
+        validate_settings(settings)?;
+
+        let mut settings = settings.clone();
+        settings.insert("version".to_string(), toml::Value::Integer(CONFIG_VERSION.into()));
+        write_atomic(path, &toml::to_string_pretty(&settings)?)
+    }
+
+    /// State file of the archlist at `archlist`, keyed by its canonical path so
+    /// every archlist resumes from its own position.
+    pub fn state_path(&self, archlist: &Path) -> Result<PathBuf> {
+        let canonical = fs::canonicalize(archlist)
+            .with_context(|| format!("Failed to resolve {}", archlist.display()))?;
+
+        let mut hasher = LineHasher::new();
+        hasher.add_line(&canonical.to_string_lossy());
+        Ok(self.state_dir.join(format!("{}.toml", hasher.hex())))
+    }
+
+    /// Loads the run state for `archlist`. An archlist without state adopts the
+    /// state migrated out of a version 1 config file, if it wasn't claimed yet.
+    pub fn load_state(&self, archlist: &Path) -> Result<RunState> {
+        let state_path = self.state_path(archlist)?;
+        let legacy_path = self.legacy_state_path();
+
+        if !state_path.exists() && legacy_path.exists() {
+            fs::create_dir_all(&self.state_dir)?;
+            fs::rename(&legacy_path, &state_path)?;
+            log::info!(
+                "Resuming {} from the position saved before per-archlist state",
+                archlist.display()
+            );
+        }
+
+        read_state(&state_path)
+    }
+
+    /// Like `load_state`, but reads the unclaimed legacy state in place instead of
+    /// claiming it, for reports and dry runs that must not touch the filesystem.
+    pub fn peek_state(&self, archlist: &Path) -> Result<RunState> {
+        let state_path = self.state_path(archlist)?;
+        if state_path.exists() {
+            read_state(&state_path)
+        } else {
+            read_state(&self.legacy_state_path())
+        }
+    }
+
+    /// Writes the run state for `archlist`.
+    pub fn save_state(&self, archlist: &Path, state: &RunState) -> Result<()> {
+        let state_path = self.state_path(archlist)?;
+        let mut state = state.clone();
+        state.archlist = fs::canonicalize(archlist).ok();
+
+        fs::create_dir_all(&self.state_dir)?;
+        write_atomic(&state_path, &toml::to_string_pretty(&state)?)
+    }
+
+    fn legacy_state_path(&self) -> PathBuf {
+        self.state_dir.join("unclaimed.toml")
+    }
+
+    fn migrate(&self) -> Result<()> {
+        migrate_config(&self.config_path, &self.legacy_state_path())
+    }
+
+    /// Archlist locations in lookup order: the working directory, then `archlist` in
+    /// the user's config home (e.g. `~/.config`), as a file or as a directory holding one.
+    pub fn archlist_candidates(&self) -> Vec<PathBuf> {
+        let mut candidates = vec![PathBuf::from(ARCHLIST_FILE)];
+
+        if let Some(config_home) = self.proj_dirs.config_dir().parent() {
+            candidates.push(config_home.join(ARCHLIST_FILE));
+            candidates.push(config_home.join(ARCHLIST_FILE).join(ARCHLIST_FILE));
+        }
+
+        candidates
+    }
+
+    /// Resolves the archlist to use: an explicit path if given, otherwise the first
+    /// existing candidate.
+    pub fn find_archlist(&self, explicit: Option<&Path>) -> Option<PathBuf> {
+        if let Some(path) = explicit {
+            return Some(path.to_path_buf());
+        }
+
+        self.archlist_candidates()
+            .into_iter()
+            .find(|candidate| candidate.is_file())
+    }
+}
+
+/// Splits a dotted setting key into its path, e.g. `limits.concurrency`. Hosts in
+/// `domains.<host>.<field>` may be quoted or bare: `domains."github.com".forge` and
+/// `domains.github.com.forge` are the same key.
+pub fn parse_key(key: &str) -> Result<Vec<String>> {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+
+    for c in key.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            '.' if !quoted => segments.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    segments.push(current);
+
+    if quoted || segments.iter().any(String::is_empty) {
+        bail!("invalid setting key '{}'", key);
+    }
+
+    if segments[0] == "domains" && segments.len() > 3 {
+        let field = segments.pop().unwrap_or_default();
+        let host = segments[1..].join(".");
+        segments = vec!["domains".to_string(), host, field];
+    }
+
+    Ok(segments)
+}
+
+/// Joins a setting path back into a key, quoting segments that contain dots.
+pub fn format_key(path: &[impl AsRef<str>]) -> String {
+    path.iter()
+        .map(|segment| {
+            let segment = segment.as_ref();
+            if segment.contains('.') {
+                format!("\"{}\"", segment)
+            } else {
+                segment.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Checks that `path` names a setting: `version`, `limits.<field>` or
+/// `domains.<host>.<field>`.
+pub fn check_key(path: &[String]) -> Result<()> {
+    let limit_keys: Vec<String> = toml::Table::try_from(LimitsConfig::default())
+        .map(|table| table.keys().cloned().collect())
+        .unwrap_or_default();
+
+    match path {
+        [key] if key == "version" => Ok(()),
+        [table, field] if table == "limits" && limit_keys.contains(field) => Ok(()),
+        [table, _, field] if table == "domains" && DOMAIN_KEYS.contains(&field.as_str()) => Ok(()),
+        _ => bail!(
+            "unknown setting '{}', expected version, limits.<{}> or domains.<host>.<{}>",
+            format_key(path),
+            limit_keys.join("|"),
+            DOMAIN_KEYS.join("|")
+        ),
+    }
+}
+
+/// Checks every key of `settings` and that the whole table deserializes as an
+/// `AppConfig`, so a bad value is caught before it is written.
+pub fn validate_settings(settings: &toml::Table) -> Result<AppConfig> {
+    for (path, _) in flatten(settings) {
+        check_key(&path)?;
+    }
+
+    toml::Value::Table(settings.clone())
+        .try_into()
+        .context("Invalid setting value")
+}
+
+/// Leaf values of `table` with their paths. Arrays are leaves.
+pub fn flatten(table: &toml::Table) -> Vec<(Vec<String>, toml::Value)> {
+    fn walk(table: &toml::Table, prefix: &mut Vec<String>, out: &mut Vec<(Vec<String>, toml::Value)>) {
+        for (key, value) in table {
+            prefix.push(key.clone());
+            match value {
+                toml::Value::Table(inner) => walk(inner, prefix, out),
+                value => out.push((prefix.clone(), value.clone())),
+            }
+            prefix.pop();
+        }
+    }
+
+    let mut out = Vec::new();
+    walk(table, &mut Vec::new(), &mut out);
+    out
+}
+
+/// Setting path an `ARCHIVE_LIST_*` env var overrides, e.g.
+/// `ARCHIVE_LIST_LIMITS__CONCURRENCY` is `limits.concurrency`.
+pub fn env_key(name: &str) -> Option<Vec<String>> {
+    let rest = name.strip_prefix(ENV_PREFIX)?.strip_prefix('_')?;
+    Some(rest.to_lowercase().split("__").map(str::to_string).collect())
+}
+
+/// `ARCHIVE_LIST_*` overrides, with `__` between nested keys.
+fn env_source() -> Environment {
+    Environment::with_prefix(ENV_PREFIX)
+        .prefix_separator("_")
+        .separator("__")
+        .try_parsing(true)
+}
+
+/// Run state stored at `path`, or the default state if there is none.
+fn read_state(path: &Path) -> Result<RunState> {
+    if !path.exists() {
+        return Ok(RunState::default());
+    }
+
+    let text =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    toml::from_str(&text).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Writes `contents` beside `path` and renames it into place, so an interrupted
+/// write leaves the old file intact instead of a truncated one.
+fn write_atomic(path: &Path, contents: &str) -> Result<()> {
+    let mut tmp_name = path.file_name().context("Path has no file name")?.to_os_string();
+    tmp_name.push(format!(".{}.tmp", std::process::id()));
+    let tmp_path = path.with_file_name(tmp_name);
+
+    fs::write(&tmp_path, contents)
+        .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, path).with_context(|| format!("Failed to replace {}", path.display()))
+}
+
+/// Brings the config file at `config_path` up to `CONFIG_VERSION`. Version 1 kept
+/// one run state for every archlist; it is parked at `legacy_state_path` until an
+/// archlist without state of its own claims it, where the saved fingerprint guards
+/// against resuming the wrong archlist.
+fn migrate_config(config_path: &Path, legacy_state_path: &Path) -> Result<()> {
+    if !config_path.exists() {
+        return Ok(());
+    }
+
+    let version = check_version(config_path)?;
+    if version == CONFIG_VERSION {
+        return Ok(());
+    }
+
+    let text = fs::read_to_string(config_path)?;
+    let mut table: toml::Table = toml::from_str(&text)
+        .with_context(|| format!("Failed to parse {}", config_path.display()))?;
+
+    let mut state = toml::Table::new();
+    for key in [
+        "lines_from_bottom",
+        "lines_from_top",
+        "bottom_fingerprint",
+        "top_fingerprint",
+    ] {
+        if let Some(value) = table.remove(key) {
+            state.insert(key.to_string(), value);
+        }
+    }
+    if !state.is_empty() {
+        if let Some(dir) = legacy_state_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        write_atomic(legacy_state_path, &toml::to_string_pretty(&state)?)?;
+    }
+
+    table.insert("version".to_string(), toml::Value::Integer(CONFIG_VERSION.into()));
+    write_atomic(config_path, &toml::to_string_pretty(&table)?)?;
+    log::info!(
+        "Migrated {} from schema version {} to {}",
+        config_path.display(),
+        version,
+        CONFIG_VERSION
+    );
+    Ok(())
+}
+
+/// Schema version of the config file at `path`, refusing files from a newer release.
+fn check_version(path: &Path) -> Result<u32> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let table: toml::Table =
+        toml::from_str(&text).with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    let version = match table.get("version") {
+        None => 1,
+        Some(toml::Value::Integer(version)) => u32::try_from(*version).unwrap_or(u32::MAX),
+        Some(_) => bail!("{}: version must be an integer", path.display()),
+    };
+
+    if version > CONFIG_VERSION {
+        bail!(
+            "{} has schema version {}, but this archive-list only understands up to {}",
+            path.display(),
+            version,
+            CONFIG_VERSION
+        );
+    }
+    Ok(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_and_domain_overrides_from_toml() {
+        let config: AppConfig = toml::from_str(
+            r#"
+version = 2
+
+[limits]
+concurrency = 20
+request_timeout_secs = 0
+
+[domains."github.com"]
+max_concurrent = 6
+
+[domains."gitlab.com"]
+
+[domains."git.corp.example"]
+forge = "forgejo"
+strategies = ["api", "html-scrape"]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.limits.concurrency, 20);
+        assert_eq!(config.limits.max_concurrent_per_domain, 2);
+        assert_eq!(config.limits.request_timeout(), None);
+        assert_eq!(config.limits.connect_timeout(), Some(Duration::from_secs(10)));
+
+        let settings = config.provider_settings();
+        assert_eq!(settings.max_concurrent("github.com"), 6);
+        assert_eq!(settings.max_concurrent("gitlab.com"), 2);
+        assert_eq!(settings.max_concurrent("codeberg.org"), 2);
+        assert_eq!(settings.max_concurrent_any_domain(), 6);
+
+        let corp = &config.domains["git.corp.example"];
+        assert_eq!(corp.forge, Some(ForgeKind::Gitea));
+        assert_eq!(
+            corp.strategies,
+            Some(vec![StrategyKind::Api, StrategyKind::HtmlScrape])
+        );
+        assert!(toml::from_str::<AppConfig>("[domains.\"x.org\"]\nstrategy = []").is_err());
+    }
+
+    #[test]
+    fn test_migrate_moves_state_out_of_version_1_config() {
+        let dir = std::env::temp_dir().join(format!(
+            "archive-list-config-{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let config_path = dir.join("config.toml");
+        let legacy_path = dir.join("state").join("unclaimed.toml");
+        fs::write(
+            &config_path,
+            "lines_from_bottom = 40\nlines_from_top = 3\n\n[limits]\nconcurrency = 4\n",
+        )
+        .unwrap();
+
+        migrate_config(&config_path, &legacy_path).unwrap();
+
+        assert_eq!(check_version(&config_path).unwrap(), CONFIG_VERSION);
+        let config: AppConfig = toml::from_str(&fs::read_to_string(&config_path).unwrap()).unwrap();
+        assert_eq!(config.limits.concurrency, 4);
+
+        let state: RunState = toml::from_str(&fs::read_to_string(&legacy_path).unwrap()).unwrap();
+        assert_eq!(state.lines_from_bottom, 40);
+        assert_eq!(state.lines_from_top, 3);
+
+        fs::write(&config_path, "version = 99\n").unwrap();
+        assert!(migrate_config(&config_path, &legacy_path).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_and_format_keys() {
+        assert_eq!(parse_key("limits.concurrency").unwrap(), vec!["limits", "concurrency"]);
+        let quoted = parse_key("domains.\"git.corp.example\".forge").unwrap();
+        assert_eq!(quoted, vec!["domains", "git.corp.example", "forge"]);
+        assert_eq!(parse_key("domains.git.corp.example.forge").unwrap(), quoted);
+        assert_eq!(format_key(&quoted), "domains.\"git.corp.example\".forge");
+        assert!(parse_key("limits..concurrency").is_err());
+        assert!(parse_key("domains.\"open.forge").is_err());
+    }
+
+    #[test]
+    fn test_check_key_against_schema() {
+        let key = |k: &str| parse_key(k).unwrap();
+        assert!(check_key(&key("limits.velocity")).is_ok());
+        assert!(check_key(&key("domains.github.com.max_concurrent")).is_ok());
+        assert!(check_key(&key("limits.speed")).is_err());
+        assert!(check_key(&key("domains.github.com.token")).is_err());
+        assert!(check_key(&key("lines_from_bottom")).is_err());
+
+        let all_fields = DomainSettings {
+            forge: Some(ForgeKind::GitHub),
+            env_var: Some(String::new()),
+            token_file: Some(PathBuf::new()),
+            token_command: Some(String::new()),
+            keyring: Some(true),
+            git_credential: Some(true),
+            api_pattern: Some(String::new()),
+            validation_url: Some(String::new()),
+            auth: Some(AuthScheme::Bearer),
+            strategies: Some(Vec::new()),
+            raw_url: Some(String::new()),
+            max_concurrent: Some(1),
+        };
+        let fields = toml::Table::try_from(all_fields).unwrap();
+        assert_eq!(fields.len(), DOMAIN_KEYS.len());
+        assert!(DOMAIN_KEYS.iter().all(|key| fields.contains_key(*key)));
+    }
+
+    #[test]
+    fn test_validate_settings_catches_bad_values() {
+        let table: toml::Table = toml::from_str("[limits]\nconcurrency = \"many\"").unwrap();
+        assert!(validate_settings(&table).is_err());
+
+        let table: toml::Table = toml::from_str("[limits]\nvelocity = 2").unwrap();
+        assert_eq!(validate_settings(&table).unwrap().limits.velocity, 2.0);
+
+        assert_eq!(
+            env_key("ARCHIVE_LIST_LIMITS__REQUEST_TIMEOUT_SECS"),
+            Some(vec!["limits".to_string(), "request_timeout_secs".to_string()])
+        );
+        assert_eq!(env_key("GITHUB_TOKEN"), None);
     }
 }
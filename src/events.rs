@@ -0,0 +1,147 @@
+use crate::stats::RunSummary;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::io::Write;
+use std::sync::Mutex;
+
+/// What happened, tagged as `"event"` in the JSON line.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum EventKind {
+    Started,
+    StrategyResolved {
+        strategy: &'static str,
+        readme_url: String,
+    },
+    Fetched {
+        bytes: u64,
+        status: u16,
+        path: String,
+    },
+    Skipped {
+        reason: &'static str,
+    },
+    Failed {
+        code: &'static str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        status: Option<u16>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        strategy: Option<&'static str>,
+        message: String,
+    },
+    RateLimited {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        remaining: Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        limit: Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        reset_at: Option<DateTime<Utc>>,
+    },
+    Summary(RunSummary),
+}
+
+#[derive(Debug, Serialize)]
+struct Event<'a> {
+    timestamp: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    domain: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<&'a str>,
+    #[serde(flatten)]
+    kind: EventKind,
+}
+
+/// Writes run events to stdout as JSON lines, or discards them when disabled.
+pub struct EventSink {
+    enabled: bool,
+    stdout: Mutex<std::io::Stdout>,
+}
+
+impl EventSink {
+    /// Enabling the sink takes over stdout: progress lines then only go to the
+    /// log file.
+    pub fn new(enabled: bool) -> Self {
+        if enabled {
+            crate::logging::reserve_stdout();
+        }
+
+        Self {
+            enabled,
+            stdout: Mutex::new(std::io::stdout()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Emits an event about `url`, tagged with its host.
+    pub fn url_event(&self, url: &str, kind: EventKind) {
+        if !self.enabled {
+            return;
+        }
+
+        let domain = url::Url::parse(url)
+            .ok()
+            .and_then(|parsed| parsed.host_str().map(str::to_string));
+        self.write(&Event {
+            timestamp: Utc::now(),
+            domain,
+            url: Some(url),
+            kind,
+        });
+    }
+
+    pub fn summary(&self, summary: &RunSummary) {
+        if !self.enabled {
+            return;
+        }
+
+        self.write(&Event {
+            timestamp: Utc::now(),
+            domain: None,
+            url: None,
+            kind: EventKind::Summary(summary.clone()),
+        });
+    }
+
+    fn write(&self, event: &Event) {
+        let line = match serde_json::to_string(event) {
+            Ok(line) => line,
+            Err(e) => {
+                log::error!("Failed to serialize event: {}", e);
+                return;
+            }
+        };
+
+        let mut stdout = self.stdout.lock().unwrap();
+        if let Err(e) = writeln!(stdout, "{}", line).and_then(|_| stdout.flush()) {
+            log::error!("Failed to write event: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_serialization() {
+        let event = Event {
+            timestamp: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            domain: Some("github.com".to_string()),
+            url: Some("https://github.com/a/b"),
+            kind: EventKind::Failed {
+                code: "NO-README",
+                status: Some(404),
+                strategy: None,
+                message: "NO-README (HTTP 404)".to_string(),
+            },
+        };
+
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"timestamp":"2023-11-14T22:13:20Z","domain":"github.com","url":"https://github.com/a/b","event":"failed","code":"NO-README","status":404,"message":"NO-README (HTTP 404)"}"#
+        );
+    }
+}
@@ -1,14 +1,219 @@
+use crate::provider::FetchError;
 use anyhow::{Context, Result};
-use std::fs::OpenOptions;
-use std::io::Write;
-
-pub fn log_failure(url: &str, error_code: &str, fail_file: &str) -> Result<()> {
-    let mut file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(fail_file)
-        .context("Failed to open .fail file")?;
-
-    writeln!(file, "{} {}", url, error_code)?;
-    Ok(())
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+pub const LEDGER_FILE: &str = "failures.jsonl";
+
+/// Code recorded when a URL that previously failed is fetched successfully.
+pub const SUCCESS_CODE: &str = "OK";
+
+/// One line of the failure ledger. The latest entry for a URL is its current outcome.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub timestamp: DateTime<Utc>,
+    pub url: String,
+    pub code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<String>,
+    pub attempts: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl LedgerEntry {
+    pub fn is_success(&self) -> bool {
+        self.code == SUCCESS_CODE
+    }
+}
+
+/// Append-only JSONL record of fetch failures, and of successes that supersede them.
+pub struct FailureLedger {
+    path: PathBuf,
+    file: Mutex<File>,
+    attempts: Mutex<HashMap<String, u32>>,
+}
+
+impl FailureLedger {
+    pub fn open(path: &Path) -> Result<Self> {
+        let attempts = read_latest(path)?
+            .into_values()
+            .filter(|entry| !entry.is_success())
+            .map(|entry| (entry.url, entry.attempts))
+            .collect();
+
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open failure ledger: {}", path.display()))?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: Mutex::new(file),
+            attempts: Mutex::new(attempts),
+        })
+    }
+
+    pub fn record_failure(&self, url: &str, error: &FetchError) -> Result<()> {
+        let attempts = {
+            let mut attempts = self.attempts.lock().unwrap();
+            let count = attempts.entry(url.to_string()).or_insert(0);
+            *count += 1;
+            *count
+        };
+
+        self.append(&LedgerEntry {
+            timestamp: Utc::now(),
+            url: url.to_string(),
+            code: error.code().to_string(),
+            status: error.status,
+            strategy: error.strategy.map(str::to_string),
+            attempts,
+            message: Some(error.to_string()),
+        })
+    }
+
+    /// Records a success, superseding earlier failures. URLs with no failure on
+    /// record are skipped, so the ledger only grows with failures.
+    pub fn record_success(&self, url: &str) -> Result<()> {
+        let attempts = match self.attempts.lock().unwrap().remove(url) {
+            Some(attempts) => attempts + 1,
+            None => return Ok(()),
+        };
+
+        self.append(&LedgerEntry {
+            timestamp: Utc::now(),
+            url: url.to_string(),
+            code: SUCCESS_CODE.to_string(),
+            status: None,
+            strategy: None,
+            attempts,
+            message: None,
+        })
+    }
+
+    fn append(&self, entry: &LedgerEntry) -> Result<()> {
+        let line = serde_json::to_string(entry)?;
+        let mut file = self.file.lock().unwrap();
+        writeln!(file, "{}", line)
+            .with_context(|| format!("Failed to write failure ledger: {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+/// Reads the latest ledger entry per URL. A missing ledger is treated as empty,
+/// and malformed lines, e.g. from an interrupted write, are skipped.
+pub fn read_latest(path: &Path) -> Result<HashMap<String, LedgerEntry>> {
+    let mut latest = HashMap::new();
+    if !path.exists() {
+        return Ok(latest);
+    }
+
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open failure ledger: {}", path.display()))?;
+
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match serde_json::from_str::<LedgerEntry>(&line) {
+            Ok(entry) => {
+                latest.insert(entry.url.clone(), entry);
+            }
+            Err(e) => log::warn!("{}:{}: Skipping malformed entry: {}", path.display(), number + 1, e),
+        }
+    }
+
+    Ok(latest)
+}
+
+/// URLs whose latest outcome is a failure, sorted by URL.
+pub fn current_failures(path: &Path) -> Result<Vec<LedgerEntry>> {
+    let mut failures: Vec<LedgerEntry> = read_latest(path)?
+        .into_values()
+        .filter(|entry| !entry.is_success())
+        .collect();
+    failures.sort_by(|a, b| a.url.cmp(&b.url));
+    Ok(failures)
+}
+
+/// Writes current failures in the legacy `.fail` format, `<url> <code>` per line.
+pub fn export_fail_file(ledger_path: &Path, fail_file: &Path) -> Result<usize> {
+    let failures = current_failures(ledger_path)?;
+
+    let mut file = File::create(fail_file)
+        .with_context(|| format!("Failed to create fail file: {}", fail_file.display()))?;
+    for entry in &failures {
+        writeln!(file, "{} {}", entry.url, entry.code)?;
+    }
+
+    Ok(failures.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::FetchErrorKind;
+
+    fn temp_ledger(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "archive-list-{}-{}.jsonl",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_success_supersedes_failure() {
+        let path = temp_ledger("supersede");
+        let ledger = FailureLedger::open(&path).unwrap();
+        let error = FetchError::new(FetchErrorKind::RateLimited);
+
+        ledger.record_failure("https://github.com/a/b", &error).unwrap();
+        ledger.record_failure("https://github.com/a/b", &error).unwrap();
+        ledger.record_failure("https://github.com/a/c", &error).unwrap();
+        ledger.record_success("https://github.com/a/b").unwrap();
+        ledger.record_success("https://github.com/a/d").unwrap();
+
+        let latest = read_latest(&path).unwrap();
+        assert_eq!(latest.len(), 2);
+        assert!(latest["https://github.com/a/b"].is_success());
+        assert_eq!(latest["https://github.com/a/b"].attempts, 3);
+
+        let failures = current_failures(&path).unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].url, "https://github.com/a/c");
+        assert_eq!(failures[0].code, "RATE-LIMIT");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_attempts_carry_over_between_runs() {
+        let path = temp_ledger("attempts");
+        let error = FetchError::new(FetchErrorKind::Timeout);
+
+        FailureLedger::open(&path).unwrap().record_failure("https://github.com/a/b", &error).unwrap();
+        FailureLedger::open(&path).unwrap().record_failure("https://github.com/a/b", &error).unwrap();
+
+        assert_eq!(read_latest(&path).unwrap()["https://github.com/a/b"].attempts, 2);
+
+        let _ = std::fs::remove_file(&path);
+    }
 }
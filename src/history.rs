@@ -0,0 +1,259 @@
+use crate::provider::strategy::extract_repo_path;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OpenFlags};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const FIREFOX_DB: &str = "places.sqlite";
+const CHROMIUM_DB: &str = "History";
+
+/// Microseconds between the Windows epoch (1601-01-01) used by Chromium and the Unix epoch.
+const CHROMIUM_EPOCH_OFFSET_MICROS: i64 = 11_644_473_600_000_000;
+
+/// First path segments on forges that are site pages rather than owners.
+const RESERVED_OWNERS: &[&str] = &[
+    "about", "api", "explore", "features", "login", "logout", "marketplace", "new",
+    "notifications", "orgs", "organizations", "pricing", "pulls", "issues", "search",
+    "settings", "signup", "sponsors", "topics", "trending", "users", "dashboard", "-",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Browser {
+    Firefox,
+    Chromium,
+}
+
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    pub url: String,
+    pub visit_count: u64,
+    pub last_visit: Option<DateTime<Utc>>,
+}
+
+/// Finds the history database for a profile directory, or accepts a database file directly.
+pub fn locate_database(profile: &Path) -> Result<(Browser, PathBuf)> {
+    if profile.is_dir() {
+        for (name, browser) in [(FIREFOX_DB, Browser::Firefox), (CHROMIUM_DB, Browser::Chromium)] {
+            let candidate = profile.join(name);
+            if candidate.is_file() {
+                return Ok((browser, candidate));
+            }
+        }
+        return Err(anyhow::anyhow!(
+            "No {} or {} found in profile: {}",
+            FIREFOX_DB,
+            CHROMIUM_DB,
+            profile.display()
+        ));
+    }
+
+    match profile.file_name().and_then(|name| name.to_str()) {
+        Some(FIREFOX_DB) => Ok((Browser::Firefox, profile.to_path_buf())),
+        Some(CHROMIUM_DB) => Ok((Browser::Chromium, profile.to_path_buf())),
+        _ => Err(anyhow::anyhow!(
+            "Not a browser profile or history database: {}",
+            profile.display()
+        )),
+    }
+}
+
+/// Reads every visited URL from a profile. The database is copied first and the
+/// copy opened read-only, so a running browser's lock doesn't get in the way.
+pub fn read_history(profile: &Path) -> Result<Vec<HistoryEntry>> {
+    let (browser, db_path) = locate_database(profile)?;
+    let snapshot = Snapshot::copy(&db_path)?;
+
+    let conn = Connection::open_with_flags(&snapshot.db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("Failed to open history copy of {}", db_path.display()))?;
+
+    let query = match browser {
+        Browser::Firefox => {
+            "SELECT url, visit_count, last_visit_date FROM moz_places WHERE visit_count > 0"
+        }
+        Browser::Chromium => {
+            "SELECT url, visit_count, last_visit_time FROM urls WHERE visit_count > 0"
+        }
+    };
+
+    let mut stmt = conn.prepare(query).context("Failed to query browser history")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, i64>(1)?,
+            row.get::<_, Option<i64>>(2)?,
+        ))
+    })?;
+
+    let mut entries = Vec::new();
+    for row in rows {
+        let (url, visit_count, visit_time) = row?;
+        let micros = match browser {
+            Browser::Firefox => visit_time,
+            Browser::Chromium => visit_time
+                .filter(|t| *t > 0)
+                .map(|t| t - CHROMIUM_EPOCH_OFFSET_MICROS),
+        };
+
+        entries.push(HistoryEntry {
+            url,
+            visit_count: visit_count.max(0) as u64,
+            last_visit: micros.and_then(DateTime::from_timestamp_micros),
+        });
+    }
+
+    log::debug!("Read {} history entries from {}", entries.len(), db_path.display());
+
+    Ok(entries)
+}
+
+/// Reduces a visited URL to its repository root, `https://<host>/<owner>/<repo>`,
+/// using the same path logic as the README strategies.
+pub fn repo_root(url: &str) -> Option<(String, String)> {
+    let parsed = url::Url::parse(url).ok()?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return None;
+    }
+
+    let host = parsed.host_str()?.to_string();
+    let bare = format!("https://{}{}", host, parsed.path());
+    let repo_path = extract_repo_path(&bare)?;
+
+    let (owner, repo) = repo_path.split_once('/')?;
+    if owner.is_empty() || repo.is_empty() || RESERVED_OWNERS.contains(&owner) {
+        return None;
+    }
+    let repo = repo.strip_suffix(".git").unwrap_or(repo);
+
+    Some((host.clone(), format!("https://{}/{}/{}", host, owner, repo)))
+}
+
+/// Whether an unconfigured host looks like a self-hosted forge.
+pub fn looks_like_forge(host: &str) -> bool {
+    let first_label = host.split('.').next().unwrap_or_default();
+
+    matches!(first_label, "git" | "gitlab" | "gitea" | "forgejo" | "code")
+        || host.contains("gitlab")
+        || host.contains("gitea")
+        || host.contains("forgejo")
+}
+
+/// Aggregates history entries into repository roots accepted by `is_forge`, ranked
+/// by total visits and then by most recent visit.
+pub fn rank_repos(entries: &[HistoryEntry], is_forge: impl Fn(&str) -> bool) -> Vec<HistoryEntry> {
+    let mut repos: HashMap<String, HistoryEntry> = HashMap::new();
+
+    for entry in entries {
+        let Some((host, root)) = repo_root(&entry.url) else {
+            continue;
+        };
+        if !is_forge(&host) {
+            continue;
+        }
+
+        let repo = repos.entry(root.clone()).or_insert_with(|| HistoryEntry {
+            url: root,
+            visit_count: 0,
+            last_visit: None,
+        });
+        repo.visit_count += entry.visit_count;
+        repo.last_visit = repo.last_visit.max(entry.last_visit);
+    }
+
+    let mut ranked: Vec<HistoryEntry> = repos.into_values().collect();
+    ranked.sort_by(|a, b| {
+        b.visit_count
+            .cmp(&a.visit_count)
+            .then(b.last_visit.cmp(&a.last_visit))
+            .then(a.url.cmp(&b.url))
+    });
+    ranked
+}
+
+/// Temporary copy of a history database and its WAL files, removed on drop.
+struct Snapshot {
+    dir: PathBuf,
+    db_path: PathBuf,
+}
+
+impl Snapshot {
+    fn copy(db_path: &Path) -> Result<Self> {
+        let dir = std::env::temp_dir().join(format!(
+            "archive-list-history-{}-{}",
+            std::process::id(),
+            Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        fs::create_dir_all(&dir)?;
+
+        let file_name = db_path
+            .file_name()
+            .ok_or_else(|| anyhow::anyhow!("Invalid database path: {}", db_path.display()))?;
+        let snapshot = Self {
+            db_path: dir.join(file_name),
+            dir,
+        };
+
+        fs::copy(db_path, &snapshot.db_path)
+            .with_context(|| format!("Failed to copy {}", db_path.display()))?;
+
+        // Recent visits may still live in the write-ahead log
+        for suffix in ["-wal", "-shm"] {
+            let mut sidecar = db_path.as_os_str().to_owned();
+            sidecar.push(suffix);
+            let sidecar = PathBuf::from(sidecar);
+            if sidecar.is_file() {
+                let mut target = snapshot.db_path.as_os_str().to_owned();
+                target.push(suffix);
+                fs::copy(&sidecar, PathBuf::from(target))
+                    .with_context(|| format!("Failed to copy {}", sidecar.display()))?;
+            }
+        }
+
+        Ok(snapshot)
+    }
+}
+
+impl Drop for Snapshot {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repo_root_reduces_deep_links() {
+        assert_eq!(
+            repo_root("https://github.com/rust-lang/rust/issues/1234?x=1#top"),
+            Some(("github.com".to_string(), "https://github.com/rust-lang/rust".to_string()))
+        );
+        assert_eq!(
+            repo_root("https://codeberg.org/forgejo/forgejo.git"),
+            Some(("codeberg.org".to_string(), "https://codeberg.org/forgejo/forgejo".to_string()))
+        );
+        assert_eq!(repo_root("https://github.com/settings/tokens"), None);
+        assert_eq!(repo_root("https://github.com/rust-lang"), None);
+        assert_eq!(repo_root("file:///home/user/a/b"), None);
+    }
+
+    #[test]
+    fn test_rank_repos_aggregates_and_orders() {
+        let at = |secs| DateTime::from_timestamp(secs, 0);
+        let entries = vec![
+            HistoryEntry { url: "https://github.com/a/one".to_string(), visit_count: 2, last_visit: at(100) },
+            HistoryEntry { url: "https://github.com/a/one/pulls".to_string(), visit_count: 3, last_visit: at(300) },
+            HistoryEntry { url: "https://github.com/a/two".to_string(), visit_count: 5, last_visit: at(200) },
+            HistoryEntry { url: "https://example.com/a/three".to_string(), visit_count: 9, last_visit: at(400) },
+        ];
+
+        let ranked = rank_repos(&entries, |host| host == "github.com");
+        let urls: Vec<&str> = ranked.iter().map(|e| e.url.as_str()).collect();
+
+        assert_eq!(urls, vec!["https://github.com/a/one", "https://github.com/a/two"]);
+        assert_eq!(ranked[0].visit_count, 5);
+        assert_eq!(ranked[0].last_visit, at(300));
+    }
+}
@@ -0,0 +1,277 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use indicatif::ProgressBar;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Log target for per-URL progress lines. These go to stdout, everything else is a
+/// diagnostic on stderr; the log file records both.
+pub const PROGRESS_TARGET: &str = "progress";
+
+/// Logs a progress line, shown on stdout unless `--quiet`.
+macro_rules! progress {
+    ($($arg:tt)+) => {
+        log::info!(target: $crate::logging::PROGRESS_TARGET, $($arg)+)
+    };
+}
+pub(crate) use progress;
+
+/// Progress bar drawn by the live display, if one is active. Console output is
+/// printed around it, and per-URL progress lines are left to the bar.
+static PROGRESS_BAR: Mutex<Option<ProgressBar>> = Mutex::new(None);
+
+pub fn set_progress_bar(bar: Option<ProgressBar>) {
+    *PROGRESS_BAR.lock().unwrap() = bar;
+}
+
+/// Set when stdout carries machine-readable output, so progress lines only go to
+/// the log file.
+static STDOUT_RESERVED: AtomicBool = AtomicBool::new(false);
+
+pub fn reserve_stdout() {
+    STDOUT_RESERVED.store(true, Ordering::Relaxed);
+}
+
+#[derive(Debug, Clone)]
+pub struct LogOptions {
+    pub verbose: u8,
+    pub quiet: bool,
+    pub log_file: Option<PathBuf>,
+    pub log_max_bytes: u64,
+    pub log_keep: usize,
+}
+
+/// Installs the logger. `RUST_LOG` takes precedence over `-v`/`-q` for diagnostics
+/// when set. Progress is shown unless `--quiet`, and the log file always records
+/// progress and at least info-level diagnostics.
+pub fn init(options: &LogOptions) -> Result<()> {
+    let level = console_level(options.verbose, options.quiet);
+    let spec = std::env::var("RUST_LOG").ok().filter(|s| !s.trim().is_empty());
+
+    let console = build_filter(spec.as_deref(), level)?;
+    let file = match &options.log_file {
+        Some(path) => Some((
+            build_filter(spec.as_deref(), level.max(LevelFilter::Info))?,
+            Mutex::new(RotatingFile::open(path, options.log_max_bytes, options.log_keep)?),
+        )),
+        None => None,
+    };
+
+    let max_level = file
+        .as_ref()
+        .map(|(filter, _)| filter.filter())
+        .unwrap_or(LevelFilter::Off)
+        .max(console.filter())
+        .max(LevelFilter::Info);
+
+    log::set_boxed_logger(Box::new(Logger {
+        console,
+        quiet: options.quiet,
+        file,
+    }))
+        .context("Failed to install logger")?;
+    log::set_max_level(max_level);
+    Ok(())
+}
+
+fn console_level(verbose: u8, quiet: bool) -> LevelFilter {
+    match (quiet, verbose) {
+        (true, _) => LevelFilter::Error,
+        (false, 0) => LevelFilter::Info,
+        (false, 1) => LevelFilter::Debug,
+        (false, _) => LevelFilter::Trace,
+    }
+}
+
+/// Our own modules log at `level`; dependencies stay at warn unless `RUST_LOG`
+/// says otherwise.
+fn build_filter(spec: Option<&str>, level: LevelFilter) -> Result<env_filter::Filter> {
+    let mut builder = env_filter::Builder::new();
+    match spec {
+        Some(spec) => {
+            builder
+                .try_parse(spec)
+                .map_err(|e| anyhow::anyhow!("Invalid RUST_LOG filter '{}': {}", spec, e))?;
+        }
+        None => {
+            builder
+                .filter_level(level.min(LevelFilter::Warn))
+                .filter_module(env!("CARGO_CRATE_NAME"), level);
+        }
+    }
+    Ok(builder.build())
+}
+
+struct Logger {
+    console: env_filter::Filter,
+    quiet: bool,
+    file: Option<(env_filter::Filter, Mutex<RotatingFile>)>,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.target() == PROGRESS_TARGET
+            || self.console.enabled(metadata)
+            || self
+                .file
+                .as_ref()
+                .is_some_and(|(filter, _)| filter.enabled(metadata))
+    }
+
+    fn log(&self, record: &Record) {
+        let progress = record.target() == PROGRESS_TARGET;
+
+        let bar = PROGRESS_BAR.lock().unwrap();
+        if progress {
+            if !self.quiet && bar.is_none() && !STDOUT_RESERVED.load(Ordering::Relaxed) {
+                println!("{}", record.args());
+            }
+        } else if self.console.matches(record) {
+            match bar.as_ref() {
+                Some(bar) => bar.suspend(|| eprintln!("{}", console_line(record))),
+                None => eprintln!("{}", console_line(record)),
+            }
+        }
+        drop(bar);
+
+        if let Some((filter, file)) = &self.file {
+            if progress || filter.matches(record) {
+                let line = format!(
+                    "{} {:<5} {}: {}\n",
+                    Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+                    record.level(),
+                    record.target(),
+                    record.args()
+                );
+                if let Err(e) = file.lock().unwrap().write(line.as_bytes()) {
+                    eprintln!("Failed to write log file: {}", e);
+                }
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Some((_, file)) = &self.file {
+            let _ = file.lock().unwrap().file.flush();
+        }
+    }
+}
+
+fn console_line(record: &Record) -> String {
+    match record.level() {
+        Level::Error => format!("error: {}", record.args()),
+        Level::Warn => format!("warning: {}", record.args()),
+        Level::Info => format!("{}", record.args()),
+        Level::Debug | Level::Trace => format!(
+            "[{} {}] {}",
+            record.level().as_str().to_lowercase(),
+            record.target(),
+            record.args()
+        ),
+    }
+}
+
+/// Append-only log file rotated by size: `<file>` becomes `<file>.1`, `<file>.1`
+/// becomes `<file>.2`, and so on, keeping `keep` old files.
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_bytes: u64,
+    keep: usize,
+}
+
+impl RotatingFile {
+    fn open(path: &Path, max_bytes: u64, keep: usize) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open log file: {}", path.display()))?;
+        let size = file.metadata()?.len();
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            size,
+            max_bytes,
+            keep,
+        })
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        if self.max_bytes > 0 && self.size > 0 && self.size + bytes.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+
+        self.file.write_all(bytes)?;
+        self.size += bytes.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+
+        if self.keep == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            for n in (1..self.keep).rev() {
+                let from = rotated_path(&self.path, n);
+                if from.exists() {
+                    std::fs::rename(&from, rotated_path(&self.path, n + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        }
+
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_keeps_limited_files() {
+        let dir = std::env::temp_dir().join(format!("archive-list-log-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("run.log");
+
+        let mut file = RotatingFile::open(&path, 10, 2).unwrap();
+        for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+            file.write(line.as_bytes()).unwrap();
+        }
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "fourth\n");
+        assert_eq!(std::fs::read_to_string(rotated_path(&path, 1)).unwrap(), "third\n");
+        assert_eq!(std::fs::read_to_string(rotated_path(&path, 2)).unwrap(), "second\n");
+        assert!(!rotated_path(&path, 3).exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_console_level_from_flags() {
+        assert_eq!(console_level(0, false), LevelFilter::Info);
+        assert_eq!(console_level(1, false), LevelFilter::Debug);
+        assert_eq!(console_level(3, false), LevelFilter::Trace);
+        assert_eq!(console_level(2, true), LevelFilter::Error);
+    }
+}
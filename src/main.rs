@@ -1,29 +1,61 @@
+mod archlist;
+mod checkpoint;
 mod cli;
 mod commands;
 mod config;
+mod events;
 mod failure;
+mod history;
+mod logging;
+mod progress;
 mod provider;
+mod shutdown;
+mod stats;
 mod util;
 mod tests;
 
 use anyhow::Result;
 use clap::{CommandFactory, Parser};
 use cli::{Cli, Commands};
+use commands::add_org::add_org;
+use commands::config::config_command;
+use commands::explain::explain;
+use commands::get::get;
+use commands::read_history::read_history;
 use commands::readme_get::readme_get;
+use commands::retry_failed::retry_failed;
+use commands::status::status;
+use std::process::ExitCode;
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> Result<ExitCode> {
     let cli = Cli::parse();
 
     if let Some(shell) = cli.completions {
         let mut cmd = Cli::command();
         clap_complete::generate(shell, &mut cmd, "archive-list", &mut std::io::stdout());
-        return Ok(());
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    logging::init(&cli.log_options())?;
+
+    // `config` loads settings itself, so it can still fix a file that doesn't load
+    if !matches!(cli.command, Commands::Config(_)) {
+        let mut config = config::ConfigManager::new()?.load()?;
+        cli.limits.apply(&mut config);
+        util::configure_providers(config.provider_settings());
     }
 
     match cli.command {
-        Commands::ReadmeGet(args) => readme_get(args).await?,
+        Commands::ReadmeGet(args) => return Ok(readme_get(args).await?.exit_code()),
+        Commands::AddOrg(args) => add_org(args).await?,
+        Commands::ReadHistory(args) => read_history(args).await?,
+        Commands::RetryFailed(args) => return Ok(retry_failed(args).await?.exit_code()),
+        Commands::Status(args) => status(args)?,
+        Commands::Get(args) => get(args).await?,
+        Commands::Explain(args) => explain(args).await?,
+        Commands::Config(args) => config_command(args, &cli.limits)?,
     }
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
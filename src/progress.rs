@@ -0,0 +1,133 @@
+use crate::logging;
+use crate::stats::{RunStats, StatsSnapshot};
+use crate::util::{get_provider_factory, mask_token, Token};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::io::IsTerminal;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+const REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Live terminal view of a run: completion, throughput, requests in flight per
+/// domain, token quotas and failure codes.
+pub struct ProgressDisplay {
+    bar: ProgressBar,
+    ticker: JoinHandle<()>,
+}
+
+impl ProgressDisplay {
+    /// Starts the display, or returns `None` when stdout is not a terminal.
+    pub fn start(stats: Arc<RunStats>) -> Option<Self> {
+        if !std::io::stdout().is_terminal() {
+            log::debug!("stdout is not a terminal, progress display disabled");
+            return None;
+        }
+
+        let bar = ProgressBar::with_draw_target(
+            Some(stats.snapshot().total as u64),
+            ProgressDrawTarget::stdout(),
+        );
+        bar.set_style(
+            ProgressStyle::with_template(
+                "{elapsed_precise} [{wide_bar}] {pos}/{len} ({per_sec}, eta {eta})\n{msg}",
+            )
+            .expect("valid progress template")
+            .progress_chars("=> "),
+        );
+        logging::set_progress_bar(Some(bar.clone()));
+
+        let ticker_bar = bar.clone();
+        let ticker = tokio::spawn(async move {
+            loop {
+                refresh(&ticker_bar, &stats).await;
+                tokio::time::sleep(REFRESH_INTERVAL).await;
+            }
+        });
+
+        Some(Self { bar, ticker })
+    }
+
+    pub fn finish(self) {
+        self.ticker.abort();
+        logging::set_progress_bar(None);
+        self.bar.finish_and_clear();
+    }
+}
+
+async fn refresh(bar: &ProgressBar, stats: &RunStats) {
+    let snapshot = stats.snapshot();
+
+    let tokens = get_provider_factory().await.tokens_by_domain().await;
+
+    bar.set_position(snapshot.completed as u64);
+    bar.set_message(render_status(&snapshot, &tokens));
+}
+
+/// Status lines shown under the progress bar.
+fn render_status(snapshot: &StatsSnapshot, tokens: &[(String, Vec<Token>)]) -> String {
+    let mut lines = Vec::new();
+
+    let in_flight: Vec<String> = snapshot
+        .in_flight
+        .iter()
+        .map(|(domain, count)| format!("{} {}", domain, count))
+        .collect();
+    lines.push(format!(
+        "in flight: {}",
+        if in_flight.is_empty() {
+            "none".to_string()
+        } else {
+            in_flight.join(", ")
+        }
+    ));
+
+    for (domain, domain_tokens) in tokens {
+        for token in domain_tokens {
+            let quota = match (token.valid, token.remaining) {
+                (Some(false), _) => "invalid".to_string(),
+                (_, Some(remaining)) => format!("{}/{}", remaining, token.limit),
+                (_, None) => "unvalidated".to_string(),
+            };
+            let reset = token
+                .reset_at
+                .map(|reset| format!("  resets {}", reset.format("%H:%M:%S")))
+                .unwrap_or_default();
+            lines.push(format!("{}  {}  {}{}", domain, mask_token(&token.value), quota, reset));
+        }
+    }
+
+    if !snapshot.failures_by_code.is_empty() {
+        let failures: Vec<String> = snapshot
+            .failures_by_code
+            .iter()
+            .map(|(code, count)| format!("{} {}", code, count))
+            .collect();
+        lines.push(format!("failures: {}", failures.join(", ")));
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_status() {
+        let mut snapshot = StatsSnapshot::default();
+        snapshot.in_flight.insert("github.com".to_string(), 2);
+        snapshot.failures_by_code.insert("NO-README", 3);
+
+        let mut token = Token::new("ghp_abcdefghijklmnopwxyz".to_string());
+        token.valid = Some(true);
+        token.remaining = Some(4321);
+        token.limit = 5000;
+
+        let status = render_status(&snapshot, &[("github.com".to_string(), vec![token])]);
+        assert_eq!(
+            status,
+            "in flight: github.com 2\ngithub.com  ghp_…wxyz  4321/5000\nfailures: NO-README 3"
+        );
+    }
+}
@@ -1,9 +1,221 @@
-use std::collections::HashMap;
+use crate::config::DomainSettings;
+use crate::provider::strategy::StrategyKind;
+use crate::util::{GitCredentials, TokenSources};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Prefix of the env var holding a host's tokens, see `token_env_var`.
+pub const TOKEN_ENV_PREFIX: &str = "ARCHIVE_LIST_TOKEN_";
+
+/// README URL used by the `raw-git` strategy for github.com.
+pub const DEFAULT_RAW_URL: &str = "https://raw.githubusercontent.com/{repo}/master/README.md";
+
+/// Forge software family, used for API calls beyond fetching a README.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForgeKind {
+    GitHub,
+    GitLab,
+    #[serde(alias = "forgejo")]
+    Gitea,
+    HuggingFace,
+}
+
+impl ForgeKind {
+    /// Base URL of the forge's REST API on `domain`.
+    pub fn api_base(&self, domain: &str) -> String {
+        match self {
+            ForgeKind::GitHub if domain == "github.com" => "https://api.github.com".to_string(),
+            ForgeKind::GitHub => format!("https://{}/api/v3", domain),
+            ForgeKind::GitLab => format!("https://{}/api/v4", domain),
+            ForgeKind::Gitea => format!("https://{}/api/v1", domain),
+            ForgeKind::HuggingFace => format!("https://{}/api", domain),
+        }
+    }
+
+    /// README API pattern of a self-hosted instance on `domain`.
+    pub fn api_pattern(&self, domain: &str) -> Option<String> {
+        match self {
+            ForgeKind::GitHub => Some(format!("{}/repos/{{repo}}/readme", self.api_base(domain))),
+            ForgeKind::GitLab => {
+                Some("/api/v4/projects/{encoded_repo}/repository/files/README.md/raw".to_string())
+            }
+            ForgeKind::Gitea => Some("/api/v1/repos/{repo}/readme".to_string()),
+            ForgeKind::HuggingFace => None,
+        }
+    }
+
+    /// README URL template of the `raw-git` strategy on `domain`. Gitea raw URLs
+    /// need the branch name, so it has none and relies on the API strategy.
+    pub fn raw_url(&self, domain: &str) -> Option<String> {
+        match self {
+            ForgeKind::GitHub if domain == "github.com" => Some(DEFAULT_RAW_URL.to_string()),
+            ForgeKind::GitHub => Some("https://{domain}/{repo}/raw/HEAD/README.md".to_string()),
+            ForgeKind::GitLab => Some("https://{domain}/{repo}/-/raw/HEAD/README.md".to_string()),
+            ForgeKind::Gitea => None,
+            ForgeKind::HuggingFace => Some("https://{domain}/{path}/raw/main/README.md".to_string()),
+        }
+    }
+
+    /// How the forge expects personal access tokens to be sent.
+    pub fn auth_scheme(&self) -> AuthScheme {
+        match self {
+            ForgeKind::GitLab => AuthScheme::PrivateToken,
+            ForgeKind::Gitea => AuthScheme::Token,
+            ForgeKind::GitHub | ForgeKind::HuggingFace => AuthScheme::Bearer,
+        }
+    }
+
+    /// URL answering with the token's user, used to validate tokens.
+    pub fn validation_url(&self, domain: &str) -> String {
+        match self {
+            ForgeKind::HuggingFace => format!("{}/whoami", self.api_base(domain)),
+            _ => format!("{}/user", self.api_base(domain)),
+        }
+    }
+}
+
+/// How a token is attached to requests. Written `auth = "private-token"` in a
+/// `[domains]` table, or `auth = { basic = "username" }`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AuthScheme {
+    /// `Authorization: Bearer <token>`.
+    #[default]
+    Bearer,
+    /// `Authorization: token <token>`, as Gitea and Forgejo accept.
+    Token,
+    /// `PRIVATE-TOKEN: <token>`, for GitLab personal access tokens.
+    PrivateToken,
+    /// `Authorization: Basic`, with this username and the token as password.
+    Basic(String),
+    /// The bare token in the named header.
+    Header(String),
+}
+
+impl AuthScheme {
+    /// Header name and value to send `token` in.
+    pub fn header(&self, token: &str) -> (String, String) {
+        match self {
+            AuthScheme::Bearer => ("Authorization".to_string(), format!("Bearer {}", token)),
+            AuthScheme::Token => ("Authorization".to_string(), format!("token {}", token)),
+            AuthScheme::PrivateToken => ("PRIVATE-TOKEN".to_string(), token.to_string()),
+            AuthScheme::Basic(username) => {
+                let credentials = base64::engine::general_purpose::STANDARD
+                    .encode(format!("{}:{}", username, token));
+                ("Authorization".to_string(), format!("Basic {}", credentials))
+            }
+            AuthScheme::Header(name) => (name.clone(), token.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for AuthScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthScheme::Bearer => write!(f, "Authorization: Bearer"),
+            AuthScheme::Token => write!(f, "Authorization: token"),
+            AuthScheme::PrivateToken => write!(f, "PRIVATE-TOKEN"),
+            AuthScheme::Basic(username) => write!(f, "Authorization: Basic as {}", username),
+            AuthScheme::Header(name) => write!(f, "{}", name),
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct DomainConfig {
-    pub env_var: &'static str,
+    /// Env var with the host's comma-separated tokens, `ARCHIVE_LIST_TOKEN_<HOST>`.
+    pub env_var: String,
+    /// Env var read instead when `env_var` is unset or empty: the configured
+    /// `env_var`, else the legacy name such as `GITHUB_TOKEN`.
+    pub fallback_env_var: Option<String>,
+    /// Token sources besides the env vars.
+    pub tokens: TokenSources,
+    /// README API path on the domain, or a full URL. Takes the same placeholders
+    /// as `raw_url`.
     pub api_pattern: Option<String>,
+    pub forge: Option<ForgeKind>,
+    pub validation_url: Option<String>,
+    /// How tokens are sent, by every strategy and token validation. Defaults to
+    /// the forge's usual scheme.
+    pub auth: AuthScheme,
+    /// Strategies in the order they are tried.
+    pub strategies: Vec<StrategyKind>,
+    /// README URL template for the `raw-git` strategy, derived from `forge`. Takes
+    /// `{domain}`, `{repo}`, `{owner}`, `{name}`, `{encoded_repo}` and `{path}`. Without one
+    /// the strategy is skipped.
+    pub raw_url: Option<String>,
+}
+
+impl DomainConfig {
+    fn new(
+        domain: &str,
+        fallback_env_var: &str,
+        api_pattern: Option<&str>,
+        forge: Option<ForgeKind>,
+    ) -> Self {
+        Self {
+            env_var: token_env_var(domain),
+            fallback_env_var: Some(fallback_env_var.to_string()),
+            tokens: TokenSources::default(),
+            api_pattern: api_pattern.map(str::to_string),
+            forge,
+            validation_url: None,
+            auth: forge.map(|f| f.auth_scheme()).unwrap_or_default(),
+            strategies: StrategyKind::DEFAULT_ORDER.to_vec(),
+            raw_url: forge.and_then(|f| f.raw_url(domain)),
+        }
+    }
+
+    /// Applies a `[domains."host"]` table from the config file over this config.
+    /// Setting only `forge` fills in that forge's API pattern, validation URL, auth
+    /// scheme and raw URL.
+    pub fn merge(mut self, domain: &str, settings: &DomainSettings) -> Self {
+        if let Some(forge) = settings.forge {
+            if self.forge != Some(forge) {
+                self.api_pattern = forge.api_pattern(domain);
+                self.validation_url = Some(forge.validation_url(domain));
+                self.auth = forge.auth_scheme();
+                self.raw_url = forge.raw_url(domain);
+            }
+            self.forge = Some(forge);
+        }
+        if let Some(env_var) = &settings.env_var {
+            self.fallback_env_var = Some(env_var.clone());
+        }
+        if let Some(token_file) = &settings.token_file {
+            self.tokens.file = Some(token_file.clone());
+        }
+        if let Some(token_command) = &settings.token_command {
+            self.tokens.command = Some(token_command.clone());
+        }
+        if let Some(keyring) = settings.keyring {
+            self.tokens.keyring_host = keyring.then(|| domain.to_string());
+        }
+        if let Some(git_credential) = settings.git_credential {
+            self.tokens.git_credential = git_credential.then(|| GitCredentials::new(domain));
+        }
+        if let Some(api_pattern) = &settings.api_pattern {
+            // An empty pattern turns the API strategy off
+            self.api_pattern = Some(api_pattern.clone()).filter(|p| !p.is_empty());
+        }
+        if let Some(validation_url) = &settings.validation_url {
+            self.validation_url = Some(validation_url.clone());
+        }
+        if let Some(auth) = &settings.auth {
+            self.auth = auth.clone();
+        }
+        if let Some(strategies) = &settings.strategies {
+            self.strategies = strategies.clone();
+        }
+        if let Some(raw_url) = &settings.raw_url {
+            // An empty template turns the raw-git strategy off
+            self.raw_url = Some(raw_url.clone()).filter(|u| !u.is_empty());
+        }
+        self
+    }
 }
 
 pub fn get_domain_configs() -> HashMap<String, DomainConfig> {
@@ -12,47 +224,210 @@ pub fn get_domain_configs() -> HashMap<String, DomainConfig> {
     domains.insert(
         "github.com".to_string(),
         DomainConfig {
-            env_var: "GITHUB_TOKEN",
-            api_pattern: Some("/repos/{repo}/readme".to_string()),
+            validation_url: Some("https://api.github.com/user".to_string()),
+            ..DomainConfig::new(
+                "github.com",
+                "GITHUB_TOKEN",
+                Some("/repos/{repo}/readme"),
+                Some(ForgeKind::GitHub),
+            )
         },
     );
 
     domains.insert(
         "gitlab.com".to_string(),
         DomainConfig {
-            env_var: "GITLAB_TOKEN",
-            api_pattern: Some("/api/v4/projects/{encoded_repo}/repository/files/README.md/raw".to_string()),
+            validation_url: Some("https://gitlab.com/api/v4/user".to_string()),
+            ..DomainConfig::new(
+                "gitlab.com",
+                "GITLAB_TOKEN",
+                Some("/api/v4/projects/{encoded_repo}/repository/files/README.md/raw"),
+                Some(ForgeKind::GitLab),
+            )
         },
     );
 
     domains.insert(
         "huggingface.co".to_string(),
         DomainConfig {
-            env_var: "HUGGINGFACE_TOKEN",
-            api_pattern: None,
+            validation_url: Some("https://huggingface.co/api/whoami".to_string()),
+            ..DomainConfig::new(
+                "huggingface.co",
+                "HUGGINGFACE_TOKEN",
+                None,
+                Some(ForgeKind::HuggingFace),
+            )
         },
     );
 
     domains.insert(
         "codeberg.org".to_string(),
         DomainConfig {
-            env_var: "CODEBERG_TOKEN",
-            api_pattern: Some("/api/v1/repos/{repo}/readme".to_string()),
+            validation_url: Some("https://codeberg.org/api/v1/user".to_string()),
+            ..DomainConfig::new(
+                "codeberg.org",
+                "CODEBERG_TOKEN",
+                Some("/api/v1/repos/{repo}/readme"),
+                Some(ForgeKind::Gitea),
+            )
         },
     );
 
     domains
 }
 
+/// Config for a host without one. Its legacy env var is named after the first
+/// host label, so `git.company.com` falls back to `GIT_TOKEN`.
 pub fn get_default_config(domain: &str) -> DomainConfig {
-    let env_var_name = domain
-        .split('.')
-        .next()
-        .unwrap_or("UNKNOWN")
-        .to_uppercase();
-    
-    DomainConfig {
-        env_var: Box::leak(format!("{}_TOKEN", env_var_name).into_boxed_str()),
-        api_pattern: None,
+    let label = domain.split('.').next().unwrap_or("UNKNOWN").to_uppercase();
+
+    DomainConfig::new(domain, &format!("{}_TOKEN", label), None, None)
+}
+
+/// Env var holding `domain`'s tokens: the host upper-cased with every character
+/// other than a letter or digit replaced by `_`, after `ARCHIVE_LIST_TOKEN_`.
+pub fn token_env_var(domain: &str) -> String {
+    let host: String = domain
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+    format!("{}{}", TOKEN_ENV_PREFIX, host)
+}
+
+/// Built-in configs with the `[domains]` tables of the config file merged over
+/// them. Hosts only found in the config file start from the default config.
+pub fn load_domain_configs(overrides: &BTreeMap<String, DomainSettings>) -> HashMap<String, DomainConfig> {
+    let mut domains = get_domain_configs();
+
+    for (domain, settings) in overrides {
+        let domain = domain.to_lowercase();
+        let config = domains
+            .remove(&domain)
+            .unwrap_or_else(|| get_default_config(&domain))
+            .merge(&domain, settings);
+        domains.insert(domain, config);
+    }
+
+    domains
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forge_fills_in_self_hosted_defaults() {
+        let settings = DomainSettings {
+            forge: Some(ForgeKind::GitLab),
+            env_var: Some("CORP_GITLAB_TOKEN".to_string()),
+            ..Default::default()
+        };
+        let overrides = BTreeMap::from([("git.corp.example".to_string(), settings)]);
+        let domains = load_domain_configs(&overrides);
+
+        let config = &domains["git.corp.example"];
+        assert_eq!(config.env_var, "ARCHIVE_LIST_TOKEN_GIT_CORP_EXAMPLE");
+        assert_eq!(config.fallback_env_var.as_deref(), Some("CORP_GITLAB_TOKEN"));
+        assert_eq!(config.forge, Some(ForgeKind::GitLab));
+        assert_eq!(
+            config.validation_url.as_deref(),
+            Some("https://git.corp.example/api/v4/user")
+        );
+        assert!(config.api_pattern.as_deref().unwrap().starts_with("/api/v4/projects/"));
+        assert_eq!(config.auth, AuthScheme::PrivateToken);
+        assert_eq!(
+            config.raw_url.as_deref(),
+            Some("https://{domain}/{repo}/-/raw/HEAD/README.md")
+        );
+        assert!(domains.contains_key("github.com"));
+    }
+
+    #[test]
+    fn test_raw_url_follows_forge() {
+        let domains = get_domain_configs();
+        assert_eq!(domains["github.com"].raw_url.as_deref(), Some(DEFAULT_RAW_URL));
+        assert_eq!(domains["codeberg.org"].raw_url, None);
+        assert_eq!(get_default_config("git.example").raw_url, None);
+
+        let settings = DomainSettings {
+            forge: Some(ForgeKind::Gitea),
+            raw_url: Some("https://{domain}/{repo}/raw/branch/main/README.md".to_string()),
+            ..Default::default()
+        };
+        let config = get_default_config("forgejo.internal").merge("forgejo.internal", &settings);
+        assert!(config.raw_url.unwrap().contains("/raw/branch/main/"));
+    }
+
+    #[test]
+    fn test_override_merges_over_built_in() {
+        let settings = DomainSettings {
+            strategies: Some(vec![StrategyKind::HtmlScrape]),
+            api_pattern: Some(String::new()),
+            ..Default::default()
+        };
+        let config = get_domain_configs()
+            .remove("codeberg.org")
+            .unwrap()
+            .merge("codeberg.org", &settings);
+
+        assert_eq!(config.env_var, "ARCHIVE_LIST_TOKEN_CODEBERG_ORG");
+        assert_eq!(config.fallback_env_var.as_deref(), Some("CODEBERG_TOKEN"));
+        assert_eq!(config.api_pattern, None);
+        assert_eq!(config.strategies, vec![StrategyKind::HtmlScrape]);
+        assert_eq!(config.forge, Some(ForgeKind::Gitea));
+    }
+
+    #[test]
+    fn test_env_var_is_scoped_to_full_host() {
+        assert_eq!(token_env_var("git.company.com"), "ARCHIVE_LIST_TOKEN_GIT_COMPANY_COM");
+        assert_eq!(token_env_var("git.other.org"), "ARCHIVE_LIST_TOKEN_GIT_OTHER_ORG");
+        assert_eq!(token_env_var("my-forge.example:8443"), "ARCHIVE_LIST_TOKEN_MY_FORGE_EXAMPLE_8443");
+
+        let config = get_default_config("www.example.com");
+        assert_eq!(config.env_var, "ARCHIVE_LIST_TOKEN_WWW_EXAMPLE_COM");
+        assert_eq!(config.fallback_env_var.as_deref(), Some("WWW_TOKEN"));
+    }
+
+    #[test]
+    fn test_auth_scheme_headers() {
+        assert_eq!(
+            AuthScheme::default().header("t0k"),
+            ("Authorization".to_string(), "Bearer t0k".to_string())
+        );
+        assert_eq!(AuthScheme::Token.header("t0k").1, "token t0k");
+        assert_eq!(
+            AuthScheme::PrivateToken.header("t0k"),
+            ("PRIVATE-TOKEN".to_string(), "t0k".to_string())
+        );
+        // base64 of "me:t0k"
+        assert_eq!(AuthScheme::Basic("me".to_string()).header("t0k").1, "Basic bWU6dDBr");
+        assert_eq!(AuthScheme::Header("X-Api-Key".to_string()).header("t0k").0, "X-Api-Key");
+
+        let table: toml::Table = toml::from_str(
+            "a = \"private-token\"\nb = { basic = \"me\" }",
+        )
+        .unwrap();
+        assert_eq!(table["a"].clone().try_into::<AuthScheme>().unwrap(), AuthScheme::PrivateToken);
+        assert_eq!(
+            table["b"].clone().try_into::<AuthScheme>().unwrap(),
+            AuthScheme::Basic("me".to_string())
+        );
+    }
+
+    #[test]
+    fn test_forge_sets_auth_scheme() {
+        let domains = get_domain_configs();
+        assert_eq!(domains["github.com"].auth, AuthScheme::Bearer);
+        assert_eq!(domains["gitlab.com"].auth, AuthScheme::PrivateToken);
+        assert_eq!(domains["codeberg.org"].auth, AuthScheme::Token);
+        assert_eq!(get_default_config("git.example").auth, AuthScheme::Bearer);
+
+        let settings = DomainSettings {
+            forge: Some(ForgeKind::Gitea),
+            auth: Some(AuthScheme::Basic("bot".to_string())),
+            ..Default::default()
+        };
+        let config = get_default_config("forgejo.internal").merge("forgejo.internal", &settings);
+        assert_eq!(config.auth, AuthScheme::Basic("bot".to_string()));
     }
 }
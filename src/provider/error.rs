@@ -0,0 +1,236 @@
+use crate::util::{detect_rate_limits, has_rate_limit_headers, RateLimitInfo};
+use reqwest::header::HeaderMap;
+use std::fmt;
+
+/// Why fetching a README failed. Each kind maps to the error code written to the
+/// failure log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FetchErrorKind {
+    NoReadme,
+    NoRepo,
+    Moved,
+    Archived,
+    AuthFailed,
+    Gated,
+    LegalTakedown,
+    RateLimited,
+    NoTokens,
+    Timeout,
+    Dns,
+    Network,
+    InvalidProvider,
+    Unknown,
+}
+
+impl FetchErrorKind {
+    pub fn code(&self) -> &'static str {
+        match self {
+            FetchErrorKind::NoReadme => "NO-README",
+            FetchErrorKind::NoRepo => "NO-REPO",
+            FetchErrorKind::Moved => "MOVED",
+            FetchErrorKind::Archived => "ARCHIVED",
+            FetchErrorKind::AuthFailed => "AUTH-FAILED",
+            FetchErrorKind::Gated => "GATED",
+            FetchErrorKind::LegalTakedown => "LEGAL-TAKEDOWN",
+            FetchErrorKind::RateLimited => "RATE-LIMIT",
+            FetchErrorKind::NoTokens => "NO-TOKENS",
+            FetchErrorKind::Timeout => "TIMEOUT",
+            FetchErrorKind::Dns => "DNS",
+            FetchErrorKind::Network => "NETWORK",
+            FetchErrorKind::InvalidProvider => "INVALID-PROVIDER",
+            FetchErrorKind::Unknown => "UNKNOWN",
+        }
+    }
+
+    /// Classifies a non-success HTTP status, using headers to tell rate limits and
+    /// gated content apart from plain authorization failures.
+    pub fn from_status(status: u16, headers: &HeaderMap) -> Self {
+        let gated = headers
+            .get("x-error-code")
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.eq_ignore_ascii_case("GatedRepo"));
+        let quota_exhausted = ["x-ratelimit-remaining", "ratelimit-remaining"]
+            .iter()
+            .filter_map(|name| headers.get(*name))
+            .any(|v| v.to_str().ok().map(str::trim) == Some("0"));
+        // GitHub's secondary rate limits are a 403 with retry-after and quota left
+        let retry_after = headers.contains_key("retry-after");
+
+        match status {
+            401 | 403 if gated => FetchErrorKind::Gated,
+            403 if quota_exhausted || retry_after => FetchErrorKind::RateLimited,
+            401 | 403 => FetchErrorKind::AuthFailed,
+            301 | 302 | 307 | 308 => FetchErrorKind::Moved,
+            404 => FetchErrorKind::NoReadme,
+            410 => FetchErrorKind::NoRepo,
+            408 | 504 => FetchErrorKind::Timeout,
+            429 => FetchErrorKind::RateLimited,
+            451 => FetchErrorKind::LegalTakedown,
+            _ => FetchErrorKind::Unknown,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct FetchError {
+    pub kind: FetchErrorKind,
+    pub status: Option<u16>,
+    pub strategy: Option<&'static str>,
+    pub token_used: bool,
+    /// Rate-limit headers on the failed response, if it carried any.
+    pub rate_limit: Option<RateLimitInfo>,
+    pub source: Option<anyhow::Error>,
+}
+
+impl FetchError {
+    pub fn new(kind: FetchErrorKind) -> Self {
+        Self {
+            kind,
+            status: None,
+            strategy: None,
+            token_used: false,
+            rate_limit: None,
+            source: None,
+        }
+    }
+
+    pub fn from_response(response: &reqwest::Response) -> Self {
+        let status = response.status().as_u16();
+        let mut error = Self::new(FetchErrorKind::from_status(status, response.headers()));
+        error.status = Some(status);
+        error.rate_limit = has_rate_limit_headers(response.headers())
+            .then(|| detect_rate_limits(response.headers()));
+        error
+    }
+
+    /// Classifies a transport failure: timeouts, DNS resolution, other connection errors.
+    pub fn from_transport(error: reqwest_middleware::Error) -> Self {
+        let kind = match &error {
+            reqwest_middleware::Error::Reqwest(e) if e.is_timeout() => FetchErrorKind::Timeout,
+            reqwest_middleware::Error::Reqwest(e) if is_dns_error(e) => FetchErrorKind::Dns,
+            reqwest_middleware::Error::Reqwest(e) if e.is_connect() => FetchErrorKind::Network,
+            _ => FetchErrorKind::Unknown,
+        };
+
+        Self::new(kind).with_source(error.into())
+    }
+
+    pub fn with_kind(mut self, kind: FetchErrorKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn with_strategy(mut self, strategy: &'static str) -> Self {
+        self.strategy = Some(strategy);
+        self
+    }
+
+    pub fn with_token_used(mut self, token_used: bool) -> Self {
+        self.token_used = token_used;
+        self
+    }
+
+    pub fn with_source(mut self, source: anyhow::Error) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.kind.code()
+    }
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())?;
+
+        if let Some(status) = self.status {
+            write!(f, " (HTTP {})", status)?;
+        }
+        if let Some(strategy) = self.strategy {
+            write!(f, " via {}", strategy)?;
+        }
+        if self.token_used {
+            write!(f, " with token")?;
+        }
+        if let Some(source) = &self.source {
+            write!(f, ": {:#}", source)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for FetchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_ref()
+            .map(|e| &**e as &(dyn std::error::Error + 'static))
+    }
+}
+
+impl From<anyhow::Error> for FetchError {
+    fn from(error: anyhow::Error) -> Self {
+        Self::new(FetchErrorKind::Unknown).with_source(error)
+    }
+}
+
+/// DNS failures surface as connect errors; the resolver's message is only in the
+/// source chain.
+fn is_dns_error(error: &reqwest::Error) -> bool {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = std::error::Error::source(error);
+
+    while let Some(err) = source {
+        let message = err.to_string().to_lowercase();
+        if message.contains("dns error")
+            || message.contains("failed to lookup address")
+            || message.contains("name or service not known")
+            || message.contains("no such host")
+        {
+            return true;
+        }
+        source = err.source();
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_classification() {
+        let headers = HeaderMap::new();
+        assert_eq!(FetchErrorKind::from_status(404, &headers), FetchErrorKind::NoReadme);
+        assert_eq!(FetchErrorKind::from_status(401, &headers), FetchErrorKind::AuthFailed);
+        assert_eq!(FetchErrorKind::from_status(429, &headers), FetchErrorKind::RateLimited);
+        assert_eq!(FetchErrorKind::from_status(451, &headers), FetchErrorKind::LegalTakedown);
+        assert_eq!(FetchErrorKind::from_status(504, &headers), FetchErrorKind::Timeout);
+        assert_eq!(FetchErrorKind::from_status(500, &headers), FetchErrorKind::Unknown);
+    }
+
+    #[test]
+    fn test_forbidden_with_exhausted_quota_is_rate_limit() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining", "0".parse().unwrap());
+        assert_eq!(FetchErrorKind::from_status(403, &headers), FetchErrorKind::RateLimited);
+    }
+
+    #[test]
+    fn test_forbidden_with_retry_after_is_rate_limit() {
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", "60".parse().unwrap());
+        headers.insert("x-ratelimit-remaining", "4999".parse().unwrap());
+        assert_eq!(FetchErrorKind::from_status(403, &headers), FetchErrorKind::RateLimited);
+        assert_eq!(FetchErrorKind::from_status(403, &HeaderMap::new()), FetchErrorKind::AuthFailed);
+    }
+
+    #[test]
+    fn test_gated_repo_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-error-code", "GatedRepo".parse().unwrap());
+        assert_eq!(FetchErrorKind::from_status(401, &headers), FetchErrorKind::Gated);
+        assert_eq!(FetchErrorKind::from_status(403, &headers), FetchErrorKind::Gated);
+    }
+}
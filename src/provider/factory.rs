@@ -1,5 +1,7 @@
-use crate::provider::domain::{get_domain_configs, get_default_config, DomainConfig};
+use crate::config::{LimitsConfig, ProviderSettings};
+use crate::provider::domain::{get_default_config, load_domain_configs, DomainConfig};
 use crate::provider::generic::Provider;
+use crate::util::Token;
 use anyhow::{Context, Result};
 use reqwest_middleware::ClientWithMiddleware;
 use std::collections::HashMap;
@@ -10,17 +12,34 @@ pub struct ProviderFactory {
     domains: HashMap<String, DomainConfig>,
     providers: Arc<RwLock<HashMap<String, Arc<Provider>>>>,
     client: Arc<ClientWithMiddleware>,
+    settings: ProviderSettings,
 }
 
 impl ProviderFactory {
     pub fn new() -> Self {
+        Self::with_settings(ProviderSettings::default())
+    }
+
+    /// Builds the shared client and providers with the configured limits, and the
+    /// built-in domains with the config file's `[domains]` tables merged over them.
+    pub fn with_settings(settings: ProviderSettings) -> Self {
         Self {
-            domains: get_domain_configs(),
+            domains: load_domain_configs(&settings.domains),
             providers: Arc::new(RwLock::new(HashMap::new())),
-            client: crate::util::create_shared_client(),
+            client: crate::util::create_shared_client(&settings),
+            settings,
         }
     }
 
+    pub fn limits(&self) -> &LimitsConfig {
+        &self.settings.limits
+    }
+
+    /// Concurrent requests allowed to `domain`, after any override.
+    pub fn max_concurrent(&self, domain: &str) -> usize {
+        self.settings.max_concurrent(domain)
+    }
+
     pub async fn get_provider(&self, url: &str) -> Result<Arc<Provider>> {
         let domain = self.extract_domain(url)?;
 
@@ -44,6 +63,7 @@ impl ProviderFactory {
             domain.clone(),
             config,
             Arc::clone(&self.client),
+            self.settings.max_concurrent(&domain),
         ));
 
         let mut providers = self.providers.write().await;
@@ -54,6 +74,30 @@ impl ProviderFactory {
         Ok(provider)
     }
 
+    /// Providers created so far, sorted by domain.
+    pub async fn providers(&self) -> Vec<Arc<Provider>> {
+        let mut providers: Vec<Arc<Provider>> =
+            self.providers.read().await.values().cloned().collect();
+        providers.sort_by(|a, b| a.domain.cmp(&b.domain));
+        providers
+    }
+
+    /// Tokens of every provider that has any, by domain.
+    pub async fn tokens_by_domain(&self) -> Vec<(String, Vec<Token>)> {
+        let mut tokens = Vec::new();
+        for provider in self.providers().await {
+            let domain_tokens = provider.tokens().await;
+            if !domain_tokens.is_empty() {
+                tokens.push((provider.domain.clone(), domain_tokens));
+            }
+        }
+        tokens
+    }
+
+    pub fn is_known_domain(&self, domain: &str) -> bool {
+        self.domains.contains_key(domain)
+    }
+
     fn extract_domain(&self, url: &str) -> Result<String> {
         let parsed = url::Url::parse(url)
             .context("Failed to parse URL")?;
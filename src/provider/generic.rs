@@ -1,32 +1,115 @@
-use crate::provider::strategy::Strategy;
-use crate::provider::domain::DomainConfig;
+use crate::provider::strategy::{
+    extract_repo_path, ApiStrategy, HtmlScrapeStrategy, RawGitStrategy, Strategy, StrategyKind,
+};
+use crate::provider::domain::{DomainConfig, ForgeKind};
+use crate::provider::error::{FetchError, FetchErrorKind};
 use crate::provider::ProviderTrait;
-use crate::util::TokenRateLimiter;
+use crate::util::{detect_rate_limits, has_rate_limit_headers, RateLimitInfo, Token, TokenRateLimiter};
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use reqwest_middleware::ClientWithMiddleware;
+use serde::Deserialize;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::{Duration, Instant};
+use tokio::sync::{RwLock, Semaphore, SemaphorePermit};
 
 #[derive(Debug)]
 pub struct Provider {
     pub domain: String,
+    pub forge: Option<ForgeKind>,
+    config: DomainConfig,
     strategies: Vec<Box<dyn Strategy>>,
     working_strategy: Arc<RwLock<Option<Box<dyn Strategy>>>>,
     token_limiter: Arc<TokenRateLimiter>,
     client: Arc<ClientWithMiddleware>,
+    /// Requests to this domain allowed at once.
+    slots: Semaphore,
+}
+
+/// What happened when one strategy was tried against a URL.
+#[derive(Debug)]
+pub struct StrategyTrace {
+    pub name: &'static str,
+    /// `None` when the strategy cannot build a README URL for this repository.
+    pub readme_url: Option<String>,
+    pub status: Option<u16>,
+    pub rate_limit: Option<RateLimitInfo>,
+    pub elapsed: Duration,
+    /// README size in bytes when accepted.
+    pub result: Result<usize, FetchError>,
+}
+
+/// Strategy chosen for a URL and the README URL it built.
+#[derive(Debug, Clone)]
+pub struct ResolvedReadme {
+    pub strategy: &'static str,
+    pub readme_url: String,
+}
+
+/// A README fetched through a resolved strategy.
+#[derive(Debug)]
+pub struct FetchedReadme {
+    pub status: u16,
+    pub rate_limit: Option<RateLimitInfo>,
+    pub content: String,
 }
 
 #[async_trait]
 impl ProviderTrait for Provider {
     async fn get_readme_url(&self, url: &str) -> anyhow::Result<String> {
+        Ok(self.resolve_readme(url).await?.readme_url)
+    }
+
+    async fn get_readme(&self, url: &str) -> Result<String, FetchError> {
+        let resolved = self.resolve_readme(url).await?;
+        Ok(self.fetch_readme(url, &resolved).await?.content)
+    }
+}
+
+impl Provider {
+    pub fn new(
+        domain: String,
+        config: DomainConfig,
+        client: Arc<ClientWithMiddleware>,
+        max_concurrent: usize,
+    ) -> Self {
+        let strategies = create_strategies(&config);
+        let mut token_limiter =
+            TokenRateLimiter::new(config.env_var.clone()).with_sources(config.tokens.clone());
+        if let Some(fallback) = &config.fallback_env_var {
+            token_limiter = token_limiter.with_fallback_env_var(fallback.clone());
+        }
+        let token_limiter = Arc::new(token_limiter);
+
+        Self {
+            domain,
+            forge: config.forge,
+            config,
+            strategies,
+            working_strategy: Arc::new(RwLock::new(None)),
+            token_limiter,
+            client,
+            slots: Semaphore::new(max_concurrent.max(1)),
+        }
+    }
+
+    pub fn config(&self) -> &DomainConfig {
+        &self.config
+    }
+
+    /// Picks the strategy for `url`: the cached working strategy if it builds a README
+    /// URL, otherwise the first strategy that does, which becomes the cached one.
+    pub async fn resolve_readme(&self, url: &str) -> Result<ResolvedReadme> {
         let working = self.working_strategy.read().await;
 
         if let Some(strategy) = working.as_ref() {
             log::debug!("{}: Trying cached strategy: {}", self.domain, strategy.name());
             if let Some(readme_url) = strategy.get_readme_url(&self.domain, url).await {
                 log::debug!("{}: Cached strategy '{}' succeeded", self.domain, strategy.name());
-                return Ok(readme_url);
+                return Ok(ResolvedReadme {
+                    strategy: strategy.name(),
+                    readme_url,
+                });
             }
             log::debug!("{}: Cached strategy '{}' failed, falling back to all strategies", self.domain, strategy.name());
         }
@@ -39,111 +122,375 @@ impl ProviderTrait for Provider {
                 log::debug!("{}: Strategy '{}' succeeded, caching for future use", self.domain, strategy.name());
                 let mut working = self.working_strategy.write().await;
                 *working = Some(strategy.clone_box());
-                return Ok(readme_url);
+                return Ok(ResolvedReadme {
+                    strategy: strategy.name(),
+                    readme_url,
+                });
             }
         }
 
         Err(anyhow::anyhow!("No strategy worked for domain: {}", self.domain))
     }
 
-    async fn fetch_url(&self, url: &str) -> anyhow::Result<reqwest::Response> {
+    /// Fetches the README `resolved` points at. A 404 is probed further to tell a
+    /// missing README from a missing, moved or archived repository.
+    pub async fn fetch_readme(
+        &self,
+        url: &str,
+        resolved: &ResolvedReadme,
+    ) -> Result<FetchedReadme, FetchError> {
+        let strategy = self
+            .strategies
+            .iter()
+            .find(|s| s.name() == resolved.strategy)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unknown strategy '{}' for {}, available: {}",
+                    resolved.strategy,
+                    self.domain,
+                    self.strategy_names().join(", ")
+                )
+            })?;
+
+        let result = match self.fetch_with_strategy(strategy.as_ref(), &resolved.readme_url).await {
+            Ok(response) => {
+                let status = response.status().as_u16();
+                let rate_limit = has_rate_limit_headers(response.headers())
+                    .then(|| detect_rate_limits(response.headers()));
+                response
+                    .text()
+                    .await
+                    .map(|content| FetchedReadme {
+                        status,
+                        rate_limit,
+                        content,
+                    })
+                    .map_err(|e| FetchError::from_transport(e.into()).with_strategy(strategy.name()))
+            }
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Err(e) if e.kind == FetchErrorKind::NoReadme && e.status == Some(404) => {
+                let kind = self.probe_repo(url).await;
+                Err(e.with_kind(kind))
+            }
+            result => result,
+        }
+    }
+
+    /// Current state of this domain's tokens, without loading or validating them.
+    pub async fn tokens(&self) -> Vec<Token> {
+        self.token_limiter.snapshot().await
+    }
+
+    /// Loads and validates this domain's tokens, returning their current state.
+    pub async fn token_states(&self) -> Vec<Token> {
         if self.token_limiter.token_count().await == 0 {
             self.token_limiter.load_tokens().await;
         }
 
-        let token = if self.token_limiter.all_tokens_exhausted().await {
+        let tokens = self.token_limiter.snapshot().await;
+        if tokens.iter().any(|t| t.valid.is_none()) {
             self.validate_tokens().await;
-            self.token_limiter.get_next_token().await
-        } else {
-            self.token_limiter.get_next_token().await
+            return self.token_limiter.snapshot().await;
+        }
+
+        tokens
+    }
+
+    /// Tries every strategy in order against `url` and reports each outcome,
+    /// without caching a working strategy.
+    pub async fn trace_strategies(&self, url: &str) -> Vec<StrategyTrace> {
+        let mut traces = Vec::new();
+
+        for strategy in &self.strategies {
+            let started = Instant::now();
+
+            let Some(readme_url) = strategy.get_readme_url(&self.domain, url).await else {
+                traces.push(StrategyTrace {
+                    name: strategy.name(),
+                    readme_url: None,
+                    status: None,
+                    rate_limit: None,
+                    elapsed: started.elapsed(),
+                    result: Err(FetchError::new(FetchErrorKind::NoReadme)
+                        .with_strategy(strategy.name())
+                        .with_source(anyhow::anyhow!("No README URL for this repository path"))),
+                });
+                continue;
+            };
+
+            let (status, rate_limit, result) =
+                match self.fetch_with_strategy(strategy.as_ref(), &readme_url).await {
+                    Ok(response) => {
+                        let status = response.status().as_u16();
+                        let rate_limit = has_rate_limit_headers(response.headers())
+                            .then(|| detect_rate_limits(response.headers()));
+                        let result = response
+                            .bytes()
+                            .await
+                            .map(|body| body.len())
+                            .map_err(|e| FetchError::from_transport(e.into()).with_strategy(strategy.name()));
+                        (Some(status), rate_limit, result)
+                    }
+                    Err(e) => (e.status, e.rate_limit.clone(), Err(e)),
+                };
+
+            traces.push(StrategyTrace {
+                name: strategy.name(),
+                readme_url: Some(readme_url),
+                status,
+                rate_limit,
+                elapsed: started.elapsed(),
+                result,
+            });
+        }
+
+        traces
+    }
+
+    /// The first strategy that builds a README URL for `url`, and that URL. Nothing
+    /// is fetched and the working strategy cache is left alone.
+    pub async fn resolve_readme_url(&self, url: &str) -> Option<(&'static str, String)> {
+        for strategy in &self.strategies {
+            if let Some(readme_url) = strategy.get_readme_url(&self.domain, url).await {
+                return Some((strategy.name(), readme_url));
+            }
+        }
+        None
+    }
+
+    pub fn strategy_names(&self) -> Vec<&'static str> {
+        self.strategies.iter().map(|s| s.name()).collect()
+    }
+
+    /// Resolves and fetches the README with one named strategy, bypassing the cached
+    /// working strategy. Returns the README URL along with the content.
+    pub async fn get_readme_with_strategy(
+        &self,
+        url: &str,
+        kind: StrategyKind,
+    ) -> Result<(String, String), FetchError> {
+        let strategy = self
+            .strategies
+            .iter()
+            .find(|s| s.kind() == kind)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Strategy '{}' is not enabled for {}, available: {}",
+                    kind.name(),
+                    self.domain,
+                    self.strategy_names().join(", ")
+                )
+            })?;
+
+        let readme_url = strategy
+            .get_readme_url(&self.domain, url)
+            .await
+            .ok_or_else(|| anyhow::anyhow!("Strategy '{}' has no README URL for {}", kind.name(), url))?;
+
+        let resolved = ResolvedReadme {
+            strategy: strategy.name(),
+            readme_url,
         };
+        let fetched = self.fetch_readme(url, &resolved).await?;
+        Ok((resolved.readme_url, fetched.content))
+    }
 
-        let working = self.working_strategy.read().await;
-        let strategy = working.as_ref()
-            .or_else(|| self.strategies.first())
-            .ok_or_else(|| anyhow::anyhow!("No strategy available"))?
-            .clone_box();
-        drop(working);
+    async fn fetch_with_strategy(
+        &self,
+        strategy: &dyn Strategy,
+        url: &str,
+    ) -> Result<reqwest::Response, FetchError> {
+        let token = self.next_token().await;
+        let token_used = token.is_some();
+        let has_tokens = self.token_limiter.token_count().await > 0;
+
+        let permit = self.acquire_slot().await;
+        let result = strategy.get_url(url, token.as_deref(), &self.client).await;
+        drop(permit);
+
+        if let Some(token_value) = &token {
+            let (status, rate_limit) = match &result {
+                Ok(response) => (
+                    Some(response.status().as_u16()),
+                    has_rate_limit_headers(response.headers())
+                        .then(|| detect_rate_limits(response.headers())),
+                ),
+                Err(e) => (e.status, e.rate_limit.clone()),
+            };
+            if let Some(status) = status {
+                self.update_token_state(status, token_value, rate_limit.as_ref()).await;
+            }
+        }
+
+        result.map_err(|e| {
+            let e = e.with_token_used(token_used);
+            if e.kind == FetchErrorKind::RateLimited && !token_used && has_tokens {
+                e.with_kind(FetchErrorKind::NoTokens)
+            } else {
+                e
+            }
+        })
+    }
+
+    /// Fetches a forge API URL with the next available token, bypassing the
+    /// README strategies. Used for listing endpoints.
+    pub async fn fetch_api(&self, url: &str) -> Result<reqwest::Response> {
+        let token = self.next_token().await;
+
+        let mut request = self.client.get(url);
+        if let Some(token) = token.as_deref() {
+            let (name, value) = self.config.auth.header(token);
+            request = request.header(name, value);
+        }
 
-        let response = strategy.get_url(url, token.as_deref(), &self.client).await?;
+        let permit = self.acquire_slot().await;
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to fetch API URL: {}", url))?;
+        drop(permit);
 
         if let Some(token_value) = token {
-            self.update_token_state(&response, &token_value).await?;
+            let rate_limit = has_rate_limit_headers(response.headers())
+                .then(|| detect_rate_limits(response.headers()));
+            self.update_token_state(response.status().as_u16(), &token_value, rate_limit.as_ref())
+                .await;
         }
 
         Ok(response)
     }
 
-    async fn get_readme(&self, url: &str) -> Result<String> {
-        let readme_url = self.get_readme_url(url).await?;
-        self.fetch_content(&readme_url).await
+    /// Waits for one of this domain's request slots.
+    async fn acquire_slot(&self) -> SemaphorePermit<'_> {
+        self.slots
+            .acquire()
+            .await
+            .expect("domain semaphore is never closed")
     }
-}
 
-impl Provider {
-    pub fn new(
-        domain: String,
-        config: DomainConfig,
-        client: Arc<ClientWithMiddleware>,
-    ) -> Self {
-        let strategies = create_strategies(&config);
-        let token_limiter = Arc::new(TokenRateLimiter::new(config.env_var));
+    async fn next_token(&self) -> Option<String> {
+        if self.token_limiter.token_count().await == 0 {
+            self.token_limiter.load_tokens().await;
+        }
 
-        Self {
-            domain,
-            strategies,
-            working_strategy: Arc::new(RwLock::new(None)),
-            token_limiter,
-            client,
+        if self.token_limiter.all_tokens_exhausted().await {
+            self.validate_tokens().await;
         }
-    }
 
-    async fn fetch_content(&self, url: &str) -> Result<String> {
-        let response = self.fetch_url(url).await?;
-        let content = response
-            .text()
-            .await
-            .context("Failed to read response content")?;
-        Ok(content)
+        self.token_limiter.get_next_token().await
     }
 
-    async fn validate_tokens(&self) {
-        let validation_url = self.get_validation_url().unwrap_or_default();
-        self.token_limiter.validate_all_tokens(&validation_url).await;
+    /// Works out why a README was not found: the repository is gone, has moved,
+    /// is archived, or simply has no README.
+    async fn probe_repo(&self, url: &str) -> FetchErrorKind {
+        let permit = self.acquire_slot().await;
+        let response = match self.client.get(url).send().await {
+            Ok(response) => response,
+            Err(_) => return FetchErrorKind::NoReadme,
+        };
+        drop(permit);
+
+        match response.status().as_u16() {
+            404 | 410 => return FetchErrorKind::NoRepo,
+            status if !(200..300).contains(&status) => return FetchErrorKind::NoReadme,
+            _ => {}
+        }
+
+        let requested = extract_repo_path(url).map(|p| p.to_lowercase());
+        let landed = extract_repo_path(response.url().as_str()).map(|p| p.to_lowercase());
+        if requested.is_some() && landed.is_some() && requested != landed {
+            return FetchErrorKind::Moved;
+        }
+
+        if self.is_archived(url).await {
+            return FetchErrorKind::Archived;
+        }
+
+        FetchErrorKind::NoReadme
     }
 
-    fn get_validation_url(&self) -> Option<String> {
-        match self.domain.as_str() {
-            "github.com" => Some("https://api.github.com/user".to_string()),
-            "gitlab.com" => Some("https://gitlab.com/api/v4/user".to_string()),
-            "codeberg.org" => Some("https://codeberg.org/api/v1/user".to_string()),
-            "huggingface.co" => Some("https://huggingface.co/api/whoami".to_string()),
-            _ => None,
+    async fn is_archived(&self, url: &str) -> bool {
+        #[derive(Deserialize)]
+        struct RepoStatus {
+            #[serde(default)]
+            archived: bool,
+        }
+
+        let (Some(forge), Some(repo_path)) = (self.forge, extract_repo_path(url)) else {
+            return false;
+        };
+
+        let api_url = match forge {
+            ForgeKind::GitHub | ForgeKind::Gitea => {
+                format!("{}/repos/{}", forge.api_base(&self.domain), repo_path)
+            }
+            ForgeKind::GitLab => format!(
+                "{}/projects/{}",
+                forge.api_base(&self.domain),
+                urlencoding::encode(&repo_path)
+            ),
+            ForgeKind::HuggingFace => return false,
+        };
+
+        match self.fetch_api(&api_url).await {
+            Ok(response) if response.status().is_success() => response
+                .json::<RepoStatus>()
+                .await
+                .map(|repo| repo.archived)
+                .unwrap_or(false),
+            _ => false,
         }
     }
 
-    async fn update_token_state(&self, response: &reqwest::Response, token_value: &str) -> Result<()> {
-        let status = response.status().as_u16();
+    async fn validate_tokens(&self) {
+        let validation_url = self.config.validation_url.clone().unwrap_or_default();
+        self.token_limiter
+            .validate_all_tokens(&self.client, &validation_url, &self.config.auth)
+            .await;
+    }
 
-        // Rate limiting is now handled by reqgov middleware
-        // Only track token validity
+    async fn update_token_state(
+        &self,
+        status: u16,
+        token_value: &str,
+        rate_limit: Option<&RateLimitInfo>,
+    ) {
+        // Pacing is handled by reqgov middleware; the limiter tracks validity, and
+        // the quota headers keep its remaining counts current for display
         if status == 401 {
             self.token_limiter.mark_invalid(token_value).await;
+            self.token_limiter.report_credential(token_value, false).await;
+            return;
+        }
+        if (200..300).contains(&status) {
+            self.token_limiter.report_credential(token_value, true).await;
+        }
+        if let Some(info) = rate_limit {
+            self.token_limiter
+                .update_token(token_value, info.remaining, info.limit, info.reset_at)
+                .await;
         }
-
-        Ok(())
     }
 }
 
 fn create_strategies(config: &DomainConfig) -> Vec<Box<dyn Strategy>> {
-    let mut strategies: Vec<Box<dyn Strategy>> = vec![
-        Box::new(crate::provider::strategy::RawGitStrategy),
-        Box::new(crate::provider::strategy::HtmlScrapeStrategy),
-    ];
-
-    if let Some(api_pattern) = &config.api_pattern {
-        strategies.insert(0, Box::new(crate::provider::strategy::ApiStrategy::new(api_pattern.clone())));
-    }
-
-    strategies
+    config
+        .strategies
+        .iter()
+        .filter_map(|kind| -> Option<Box<dyn Strategy>> {
+            match kind {
+                StrategyKind::Api => config.api_pattern.as_ref().map(|api_pattern| {
+                    Box::new(ApiStrategy::new(api_pattern.clone(), config.auth.clone()))
+                        as Box<dyn Strategy>
+                }),
+                StrategyKind::RawGit => config.raw_url.as_ref().map(|raw_url| {
+                    Box::new(RawGitStrategy::new(raw_url.clone())) as Box<dyn Strategy>
+                }),
+                StrategyKind::HtmlScrape => Some(Box::new(HtmlScrapeStrategy)),
+            }
+        })
+        .collect()
 }
@@ -0,0 +1,306 @@
+use crate::provider::domain::ForgeKind;
+use crate::provider::generic::Provider;
+use anyhow::{Context, Result};
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::Deserialize;
+
+const GITHUB_PER_PAGE: u32 = 100;
+const GITLAB_PER_PAGE: u32 = 100;
+const GITEA_PER_PAGE: u32 = 50;
+const HUGGINGFACE_PER_PAGE: u32 = 1000;
+
+#[derive(Debug, Clone, Default)]
+pub struct ListOptions {
+    pub include_forks: bool,
+    pub include_archived: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct RepoEntry {
+    pub url: String,
+    pub fork: bool,
+    pub archived: bool,
+}
+
+#[derive(Deserialize)]
+struct ForgeRepo {
+    html_url: String,
+    #[serde(default)]
+    fork: bool,
+    #[serde(default)]
+    archived: bool,
+}
+
+#[derive(Deserialize)]
+struct GitlabProject {
+    web_url: String,
+    #[serde(default)]
+    archived: bool,
+    #[serde(default)]
+    forked_from_project: Option<IgnoredAny>,
+}
+
+/// Hugging Face repository kinds: listing endpoint and URL path prefix.
+const HUGGINGFACE_KINDS: [(&str, &str); 3] =
+    [("models", ""), ("datasets", "datasets/"), ("spaces", "spaces/")];
+
+#[derive(Deserialize)]
+struct HuggingFaceRepo {
+    id: String,
+}
+
+/// Lists every repository owned by the organization, group or author at `owner_url`.
+pub async fn list_repos(
+    provider: &Provider,
+    owner_url: &str,
+    options: &ListOptions,
+) -> Result<Vec<RepoEntry>> {
+    let forge = provider.forge.ok_or_else(|| {
+        anyhow::anyhow!("Listing repositories is not supported for domain: {}", provider.domain)
+    })?;
+    let segments = owner_segments(owner_url)?;
+
+    let entries = match forge {
+        ForgeKind::GitHub => list_github(provider, &segments[0]).await?,
+        ForgeKind::GitLab => list_gitlab(provider, &segments.join("/")).await?,
+        ForgeKind::Gitea => list_gitea(provider, &segments[0]).await?,
+        ForgeKind::HuggingFace => list_huggingface(provider, &segments[0]).await?,
+    };
+
+    log::debug!("{}: Listed {} repositories for {}", provider.domain, entries.len(), owner_url);
+
+    Ok(entries
+        .into_iter()
+        .filter(|entry| options.include_forks || !entry.fork)
+        .filter(|entry| options.include_archived || !entry.archived)
+        .collect())
+}
+
+/// Splits an owner URL into its path segments, dropping the `orgs/` and `groups/`
+/// prefixes forges use for organization pages and anything after GitLab's `/-/`.
+fn owner_segments(owner_url: &str) -> Result<Vec<String>> {
+    let parsed = url::Url::parse(owner_url).context("Failed to parse URL")?;
+
+    let mut segments: Vec<String> = parsed
+        .path_segments()
+        .map(|segments| {
+            segments
+                .take_while(|s| *s != "-")
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if matches!(segments.first().map(String::as_str), Some("orgs") | Some("groups")) {
+        segments.remove(0);
+    }
+
+    if segments.is_empty() {
+        return Err(anyhow::anyhow!("URL has no organization or user: {}", owner_url));
+    }
+
+    Ok(segments)
+}
+
+async fn list_github(provider: &Provider, owner: &str) -> Result<Vec<RepoEntry>> {
+    let api_base = ForgeKind::GitHub.api_base(&provider.domain);
+
+    let org_url = format!("{}/orgs/{}/repos", api_base, owner);
+    let repos = match paginate::<ForgeRepo>(provider, &org_url, "per_page", GITHUB_PER_PAGE).await? {
+        Some(repos) => repos,
+        None => {
+            let user_url = format!("{}/users/{}/repos", api_base, owner);
+            paginate::<ForgeRepo>(provider, &user_url, "per_page", GITHUB_PER_PAGE)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("No organization or user named {}", owner))?
+        }
+    };
+
+    Ok(repos.into_iter().map(RepoEntry::from).collect())
+}
+
+async fn list_gitlab(provider: &Provider, group: &str) -> Result<Vec<RepoEntry>> {
+    let api_base = ForgeKind::GitLab.api_base(&provider.domain);
+    let encoded = urlencoding::encode(group);
+
+    let group_url = format!("{}/groups/{}/projects?include_subgroups=true", api_base, encoded);
+    let projects = match paginate::<GitlabProject>(provider, &group_url, "per_page", GITLAB_PER_PAGE).await? {
+        Some(projects) => projects,
+        None => {
+            let user_url = format!("{}/users/{}/projects", api_base, encoded);
+            paginate::<GitlabProject>(provider, &user_url, "per_page", GITLAB_PER_PAGE)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("No group or user named {}", group))?
+        }
+    };
+
+    Ok(projects
+        .into_iter()
+        .map(|project| RepoEntry {
+            url: project.web_url,
+            fork: project.forked_from_project.is_some(),
+            archived: project.archived,
+        })
+        .collect())
+}
+
+async fn list_gitea(provider: &Provider, owner: &str) -> Result<Vec<RepoEntry>> {
+    let api_base = ForgeKind::Gitea.api_base(&provider.domain);
+
+    let org_url = format!("{}/orgs/{}/repos", api_base, owner);
+    let repos = match paginate::<ForgeRepo>(provider, &org_url, "limit", GITEA_PER_PAGE).await? {
+        Some(repos) => repos,
+        None => {
+            let user_url = format!("{}/users/{}/repos", api_base, owner);
+            paginate::<ForgeRepo>(provider, &user_url, "limit", GITEA_PER_PAGE)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("No organization or user named {}", owner))?
+        }
+    };
+
+    Ok(repos.into_iter().map(RepoEntry::from).collect())
+}
+
+/// Lists the author's models, datasets and Spaces.
+async fn list_huggingface(provider: &Provider, author: &str) -> Result<Vec<RepoEntry>> {
+    let api_base = ForgeKind::HuggingFace.api_base(&provider.domain);
+    let mut entries = Vec::new();
+
+    for (kind, prefix) in HUGGINGFACE_KINDS {
+        let mut next = Some(with_query(
+            &format!("{}/{}", api_base, kind),
+            &[("author", author.to_string()), ("limit", HUGGINGFACE_PER_PAGE.to_string())],
+        )?);
+
+        // Hugging Face pages with cursors carried in the Link header
+        while let Some(url) = next {
+            let response = provider
+                .fetch_api(&url)
+                .await?
+                .error_for_status()
+                .with_context(|| format!("Listing request failed: {}", url))?;
+
+            next = next_link(response.headers());
+
+            let repos: Vec<HuggingFaceRepo> = response
+                .json()
+                .await
+                .context("Failed to parse listing response")?;
+
+            entries.extend(repos.into_iter().map(|repo| RepoEntry {
+                url: format!("https://{}/{}{}", provider.domain, prefix, repo.id),
+                fork: false,
+                archived: false,
+            }));
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Fetches numbered pages until a short page is returned. Returns `None` when the
+/// first page is a 404, so callers can fall back from org to user endpoints.
+async fn paginate<T: DeserializeOwned>(
+    provider: &Provider,
+    base_url: &str,
+    per_page_param: &str,
+    per_page: u32,
+) -> Result<Option<Vec<T>>> {
+    let mut items = Vec::new();
+    let mut page = 1u32;
+
+    loop {
+        let url = with_query(
+            base_url,
+            &[(per_page_param, per_page.to_string()), ("page", page.to_string())],
+        )?;
+
+        let response = provider.fetch_api(&url).await?;
+        if page == 1 && response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let response = response
+            .error_for_status()
+            .with_context(|| format!("Listing request failed: {}", url))?;
+
+        let batch: Vec<T> = response
+            .json()
+            .await
+            .context("Failed to parse listing response")?;
+
+        let count = batch.len();
+        items.extend(batch);
+
+        if count < per_page as usize {
+            break;
+        }
+        page += 1;
+    }
+
+    Ok(Some(items))
+}
+
+fn with_query(base_url: &str, pairs: &[(&str, String)]) -> Result<String> {
+    let mut url = url::Url::parse(base_url).context("Failed to parse URL")?;
+    {
+        let mut query = url.query_pairs_mut();
+        for (key, value) in pairs {
+            query.append_pair(key, value);
+        }
+    }
+    Ok(url.to_string())
+}
+
+fn next_link(headers: &HeaderMap) -> Option<String> {
+    let link = headers.get("link")?.to_str().ok()?;
+
+    link.split(',')
+        .find(|part| part.contains("rel=\"next\""))
+        .and_then(|part| {
+            let start = part.find('<')? + 1;
+            let end = part.find('>')?;
+            Some(part[start..end].to_string())
+        })
+}
+
+impl From<ForgeRepo> for RepoEntry {
+    fn from(repo: ForgeRepo) -> Self {
+        Self {
+            url: repo.html_url,
+            fork: repo.fork,
+            archived: repo.archived,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_owner_segments_strips_org_prefixes() {
+        assert_eq!(owner_segments("https://github.com/rust-lang").unwrap(), vec!["rust-lang"]);
+        assert_eq!(owner_segments("https://github.com/orgs/rust-lang/repositories").unwrap(), vec!["rust-lang", "repositories"]);
+        assert_eq!(owner_segments("https://gitlab.com/groups/gnome/world").unwrap(), vec!["gnome", "world"]);
+        assert_eq!(owner_segments("https://gitlab.com/gnome/world/-/shared").unwrap(), vec!["gnome", "world"]);
+        assert!(owner_segments("https://codeberg.org/").is_err());
+    }
+
+    #[test]
+    fn test_next_link_parses_rel_next() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "link",
+            "<https://huggingface.co/api/models?cursor=abc>; rel=\"next\"".parse().unwrap(),
+        );
+        assert_eq!(
+            next_link(&headers),
+            Some("https://huggingface.co/api/models?cursor=abc".to_string())
+        );
+        assert_eq!(next_link(&HeaderMap::new()), None);
+    }
+}
@@ -1,8 +1,11 @@
 pub mod strategy;
 pub mod domain;
+pub mod error;
 pub mod generic;
 pub mod factory;
+pub mod listing;
 
+pub use error::{FetchError, FetchErrorKind};
 pub use factory::ProviderFactory;
 
 use async_trait::async_trait;
@@ -10,6 +13,5 @@ use async_trait::async_trait;
 #[async_trait]
 pub trait ProviderTrait: Send + Sync {
     async fn get_readme_url(&self, url: &str) -> anyhow::Result<String>;
-    async fn fetch_url(&self, url: &str) -> anyhow::Result<reqwest::Response>;
-    async fn get_readme(&self, url: &str) -> anyhow::Result<String>;
+    async fn get_readme(&self, url: &str) -> Result<String, FetchError>;
 }
@@ -1,11 +1,41 @@
-use anyhow::{Context, Result};
+use crate::provider::domain::AuthScheme;
+use crate::provider::error::{FetchError, FetchErrorKind};
 use async_trait::async_trait;
+use clap::ValueEnum;
 use reqwest_middleware::ClientWithMiddleware;
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
+/// The README strategies, as named in `--strategy` and a domain's `strategies` list.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum StrategyKind {
+    Api,
+    RawGit,
+    HtmlScrape,
+}
+
+impl StrategyKind {
+    /// Order strategies are tried in unless a domain sets its own.
+    pub const DEFAULT_ORDER: [StrategyKind; 3] =
+        [StrategyKind::Api, StrategyKind::RawGit, StrategyKind::HtmlScrape];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            StrategyKind::Api => "api",
+            StrategyKind::RawGit => "raw-git",
+            StrategyKind::HtmlScrape => "html-scrape",
+        }
+    }
+}
+
 #[async_trait]
 pub trait Strategy: Send + Sync + std::fmt::Debug {
-    fn name(&self) -> &'static str;
+    fn kind(&self) -> StrategyKind;
+
+    fn name(&self) -> &'static str {
+        self.kind().name()
+    }
 
     async fn get_readme_url(&self, domain: &str, url: &str) -> Option<String>;
 
@@ -14,32 +44,36 @@ pub trait Strategy: Send + Sync + std::fmt::Debug {
         url: &str,
         token: Option<&str>,
         client: &Arc<ClientWithMiddleware>,
-    ) -> Result<reqwest::Response>;
+    ) -> Result<reqwest::Response, FetchError>;
 
     fn clone_box(&self) -> Box<dyn Strategy>;
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ApiStrategy {
     api_pattern: String,
+    auth: AuthScheme,
 }
 
 impl ApiStrategy {
-    pub fn new(api_pattern: String) -> Self {
-        Self { api_pattern }
+    pub fn new(api_pattern: String, auth: AuthScheme) -> Self {
+        Self { api_pattern, auth }
     }
 }
 
 #[async_trait]
 impl Strategy for ApiStrategy {
-    fn name(&self) -> &'static str {
-        "api"
+    fn kind(&self) -> StrategyKind {
+        StrategyKind::Api
     }
 
     async fn get_readme_url(&self, domain: &str, url: &str) -> Option<String> {
-        let repo_path = extract_repo_path(url)?;
-        let api_url = self.api_pattern.replace("{repo}", &repo_path);
-        Some(format!("https://{}{}", domain, api_url))
+        let api_url = expand_template(&self.api_pattern, domain, url)?;
+        if api_url.starts_with("https://") || api_url.starts_with("http://") {
+            Some(api_url)
+        } else {
+            Some(format!("https://{}{}", domain, api_url))
+        }
     }
 
     async fn get_url(
@@ -47,58 +81,60 @@ impl Strategy for ApiStrategy {
         url: &str,
         token: Option<&str>,
         client: &Arc<ClientWithMiddleware>,
-    ) -> Result<reqwest::Response> {
+    ) -> Result<reqwest::Response, FetchError> {
         let mut request = client.get(url);
 
         if let Some(token) = token {
-            request = request.header("Authorization", format!("Bearer {}", token));
+            let (name, value) = self.auth.header(token);
+            request = request.header(name, value);
         }
 
-        request
+        let response = request
             .send()
             .await
-            .context("Failed to fetch URL via API strategy")
+            .map_err(|e| FetchError::from_transport(e).with_strategy(self.name()))?;
+
+        check_status(response, self.name())
     }
 
     fn clone_box(&self) -> Box<dyn Strategy> {
-        Box::new(ApiStrategy {
-            api_pattern: self.api_pattern.clone(),
-        })
+        Box::new(self.clone())
     }
 }
 
-#[derive(Debug)]
-pub struct RawGitStrategy;
+#[derive(Debug, Clone)]
+pub struct RawGitStrategy {
+    raw_url: String,
+}
+
+impl RawGitStrategy {
+    pub fn new(raw_url: String) -> Self {
+        Self { raw_url }
+    }
+}
 
 #[async_trait]
 impl Strategy for RawGitStrategy {
-    fn name(&self) -> &'static str {
-        "raw-git"
+    fn kind(&self) -> StrategyKind {
+        StrategyKind::RawGit
     }
 
-    async fn get_readme_url(&self, _domain: &str, url: &str) -> Option<String> {
-        let repo_path = extract_repo_path(url)?;
-        let parts: Vec<&str> = repo_path.split('/').collect();
-        if parts.len() != 2 {
-            return None;
-        }
-
-        Some(format!(
-            "https://raw.githubusercontent.com/{}/master/README.md",
-            repo_path
-        ))
+    async fn get_readme_url(&self, domain: &str, url: &str) -> Option<String> {
+        expand_template(&self.raw_url, domain, url)
     }
 
-    async fn get_url(&self, url: &str, _token: Option<&str>, client: &Arc<ClientWithMiddleware>) -> Result<reqwest::Response> {
-        client
+    async fn get_url(&self, url: &str, _token: Option<&str>, client: &Arc<ClientWithMiddleware>) -> Result<reqwest::Response, FetchError> {
+        let response = client
             .get(url)
             .send()
             .await
-            .context("Failed to fetch URL via raw Git strategy")
+            .map_err(|e| FetchError::from_transport(e).with_strategy(self.name()))?;
+
+        check_status(response, self.name())
     }
 
     fn clone_box(&self) -> Box<dyn Strategy> {
-        Box::new(RawGitStrategy)
+        Box::new(self.clone())
     }
 }
 
@@ -107,27 +143,40 @@ pub struct HtmlScrapeStrategy;
 
 #[async_trait]
 impl Strategy for HtmlScrapeStrategy {
-    fn name(&self) -> &'static str {
-        "html-scrape"
+    fn kind(&self) -> StrategyKind {
+        StrategyKind::HtmlScrape
     }
 
     async fn get_readme_url(&self, _domain: &str, url: &str) -> Option<String> {
         Some(url.to_string())
     }
 
-    async fn get_url(&self, url: &str, _token: Option<&str>, client: &Arc<ClientWithMiddleware>) -> Result<reqwest::Response> {
+    async fn get_url(&self, url: &str, _token: Option<&str>, client: &Arc<ClientWithMiddleware>) -> Result<reqwest::Response, FetchError> {
         let response = client
             .get(url)
             .send()
             .await
-            .context("Failed to fetch URL via HTML scrape strategy")?;
+            .map_err(|e| FetchError::from_transport(e).with_strategy(self.name()))?;
+        let response = check_status(response, self.name())?;
+        let status = response.status().as_u16();
 
-        let html = response.text().await.ok().ok_or_else(|| anyhow::anyhow!("Failed to read response"))?;
+        let html = response.text().await.map_err(|e| {
+            FetchError::from_transport(e.into()).with_strategy(self.name())
+        })?;
 
         if html.contains("README") {
-            Ok(client.get(url).send().await?)
+            let response = client
+                .get(url)
+                .send()
+                .await
+                .map_err(|e| FetchError::from_transport(e).with_strategy(self.name()))?;
+            check_status(response, self.name())
         } else {
-            Err(anyhow::anyhow!("README not found in HTML"))
+            let mut error = FetchError::new(FetchErrorKind::NoReadme)
+                .with_strategy(self.name())
+                .with_source(anyhow::anyhow!("README not found in HTML"));
+            error.status = Some(status);
+            Err(error)
         }
     }
 
@@ -136,7 +185,36 @@ impl Strategy for HtmlScrapeStrategy {
     }
 }
 
-fn extract_repo_path(url: &str) -> Option<String> {
+/// Passes successful responses through and classifies the rest.
+fn check_status(response: reqwest::Response, strategy: &'static str) -> Result<reqwest::Response, FetchError> {
+    if response.status().is_success() {
+        Ok(response)
+    } else {
+        Err(FetchError::from_response(&response).with_strategy(strategy))
+    }
+}
+
+/// Fills a URL template for the repository at `url`: `{domain}`, `{repo}` as
+/// `owner/name`, `{owner}`, `{name}`, `{encoded_repo}` as the URL-encoded path, and
+/// `{path}` as `{repo}` keeping Hugging Face's `datasets/` or `spaces/` prefix.
+/// `None` when `url` has no repository path.
+pub fn expand_template(template: &str, domain: &str, url: &str) -> Option<String> {
+    let repo_path = extract_repo_path(url)?;
+    let (owner, name) = repo_path.split_once('/')?;
+    let path = typed_repo_path(url).unwrap_or_else(|| repo_path.clone());
+
+    Some(
+        template
+            .replace("{domain}", domain)
+            .replace("{path}", &path)
+            .replace("{encoded_repo}", &urlencoding::encode(&repo_path))
+            .replace("{repo}", &repo_path)
+            .replace("{owner}", owner)
+            .replace("{name}", name),
+    )
+}
+
+pub fn extract_repo_path(url: &str) -> Option<String> {
     let url = url.trim_end_matches('/');
     let parts: Vec<&str> = url.split('/').collect();
 
@@ -149,3 +227,50 @@ fn extract_repo_path(url: &str) -> Option<String> {
 
     Some(format!("{}/{}", owner, repo))
 }
+
+/// `owner/name` of `url`, prefixed with `datasets/` or `spaces/` when the URL has
+/// one, as Hugging Face dataset and Space URLs do.
+fn typed_repo_path(url: &str) -> Option<String> {
+    let parsed = url::Url::parse(url).ok()?;
+    let segments: Vec<&str> = parsed.path_segments()?.filter(|s| !s.is_empty()).collect();
+
+    let len = match segments.first() {
+        Some(&"datasets") | Some(&"spaces") => 3,
+        _ => 2,
+    };
+    (segments.len() >= len).then(|| segments[..len].join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_template() {
+        let url = "https://git.corp.example/team/tool";
+        assert_eq!(
+            expand_template("https://{domain}/{repo}/-/raw/HEAD/README.md", "git.corp.example", url)
+                .as_deref(),
+            Some("https://git.corp.example/team/tool/-/raw/HEAD/README.md")
+        );
+        assert_eq!(
+            expand_template("/api/v4/projects/{encoded_repo}", "git.corp.example", url).as_deref(),
+            Some("/api/v4/projects/team%2Ftool")
+        );
+        assert_eq!(
+            expand_template("{owner} {name}", "git.corp.example", url).as_deref(),
+            Some("team tool")
+        );
+        assert_eq!(expand_template("{repo}", "git.corp.example", "https://git.corp.example/"), None);
+
+        let raw = "https://{domain}/{path}/raw/main/README.md";
+        assert_eq!(
+            expand_template(raw, "huggingface.co", "https://huggingface.co/datasets/org/set").as_deref(),
+            Some("https://huggingface.co/datasets/org/set/raw/main/README.md")
+        );
+        assert_eq!(
+            expand_template(raw, "huggingface.co", "https://huggingface.co/org/model/tree/main").as_deref(),
+            Some("https://huggingface.co/org/model/raw/main/README.md")
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use crate::stats::{AbortReason, RunStats};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// How long in-flight requests may keep running after a shutdown signal.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(30);
+
+/// Exit status after a forced second interrupt, as for SIGINT.
+const FORCED_EXIT_CODE: i32 = 130;
+
+/// Graceful shutdown on SIGINT or SIGTERM. The first signal aborts the run so no
+/// new URLs are scheduled; a second one exits immediately.
+pub struct Shutdown {
+    requested: AtomicBool,
+    notify: Notify,
+}
+
+impl Shutdown {
+    /// Starts listening for signals for the rest of the process.
+    pub fn listen(stats: Arc<RunStats>) -> Arc<Self> {
+        let shutdown = Arc::new(Self {
+            requested: AtomicBool::new(false),
+            notify: Notify::new(),
+        });
+
+        let handler = Arc::clone(&shutdown);
+        tokio::spawn(async move {
+            if let Err(e) = wait_for_signal().await {
+                log::error!("Failed to listen for signals: {}", e);
+                return;
+            }
+            log::warn!(
+                "Interrupted, finishing in-flight requests for up to {}s. Interrupt again to exit immediately",
+                SHUTDOWN_GRACE.as_secs()
+            );
+            stats.abort(AbortReason::Interrupted);
+            handler.requested.store(true, Ordering::SeqCst);
+            handler.notify.notify_waiters();
+
+            if wait_for_signal().await.is_ok() {
+                log::error!("Interrupted again, exiting without saving");
+                std::process::exit(FORCED_EXIT_CODE);
+            }
+        });
+
+        shutdown
+    }
+
+    /// Runs `work` to completion, or, once a shutdown is requested, for at most
+    /// the grace period. Work still running then is dropped.
+    pub async fn drain<F: Future>(&self, work: F) {
+        tokio::select! {
+            _ = work => {}
+            _ = self.grace_expired() => {
+                log::warn!(
+                    "In-flight requests still running after {}s, abandoning them",
+                    SHUTDOWN_GRACE.as_secs()
+                );
+            }
+        }
+    }
+
+    async fn grace_expired(&self) {
+        let notified = self.notify.notified();
+        if !self.requested.load(Ordering::SeqCst) {
+            notified.await;
+        }
+        tokio::time::sleep(SHUTDOWN_GRACE).await;
+    }
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() -> std::io::Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = signal(SignalKind::terminate())?;
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => result,
+        _ = terminate.recv() => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() -> std::io::Result<()> {
+    tokio::signal::ctrl_c().await
+}
@@ -0,0 +1,444 @@
+use crate::provider::FetchErrorKind;
+use crate::util::{mask_token, Token};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::process::ExitCode;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How one archlist URL ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Downloaded { bytes: u64 },
+    Skipped,
+    Failed(&'static str),
+}
+
+/// Why a run stopped scheduling URLs before reaching the end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AbortReason {
+    MaxFailures,
+    TokensExhausted,
+    Interrupted,
+}
+
+/// How a run ended, mapped to the process exit status. Fatal errors exit with 1
+/// through `main`'s error path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RunStatus {
+    Success,
+    SomeFailed,
+    TokensExhausted,
+    TooManyFailures,
+    Interrupted,
+}
+
+impl RunStatus {
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            RunStatus::Success => ExitCode::SUCCESS,
+            RunStatus::SomeFailed => ExitCode::from(2),
+            RunStatus::TokensExhausted => ExitCode::from(3),
+            RunStatus::TooManyFailures => ExitCode::from(4),
+            RunStatus::Interrupted => ExitCode::from(130),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OutcomeCounts {
+    pub downloaded: usize,
+    pub skipped: usize,
+    pub failed: usize,
+}
+
+impl OutcomeCounts {
+    fn total(&self) -> usize {
+        self.downloaded + self.skipped + self.failed
+    }
+
+    fn add(&mut self, outcome: Outcome) {
+        match outcome {
+            Outcome::Downloaded { .. } => self.downloaded += 1,
+            Outcome::Skipped => self.skipped += 1,
+            Outcome::Failed(_) => self.failed += 1,
+        }
+    }
+}
+
+/// Point-in-time copy of a run's counters.
+#[derive(Debug, Clone, Default)]
+pub struct StatsSnapshot {
+    pub total: usize,
+    pub completed: usize,
+    pub downloaded: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub bytes: u64,
+    pub in_flight: BTreeMap<String, usize>,
+    pub failures_by_code: BTreeMap<&'static str, usize>,
+    pub domains: BTreeMap<String, OutcomeCounts>,
+    pub strategies: BTreeMap<&'static str, OutcomeCounts>,
+    /// Domains that ran out of tokens; their remaining URLs are skipped.
+    pub exhausted_domains: BTreeSet<String>,
+    pub aborted: Option<AbortReason>,
+    pub elapsed: Duration,
+}
+
+/// Counters for a run, shared by the fetch tasks and whatever displays them. Also
+/// decides when the run should stop early.
+pub struct RunStats {
+    started: Instant,
+    max_failures: Option<usize>,
+    /// URLs of the run per domain, see `with_urls`.
+    planned: BTreeMap<String, usize>,
+    inner: Mutex<StatsSnapshot>,
+}
+
+impl RunStats {
+    pub fn new(total: usize, max_failures: Option<usize>) -> Self {
+        Self {
+            started: Instant::now(),
+            max_failures,
+            planned: BTreeMap::new(),
+            inner: Mutex::new(StatsSnapshot {
+                total,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Counts the run's URLs per domain, so running out of tokens on one domain
+    /// only aborts the run once every domain with URLs left is out of them too.
+    /// Without it, the first `NO-TOKENS` failure aborts.
+    pub fn with_urls<'a>(mut self, urls: impl IntoIterator<Item = &'a str>) -> Self {
+        for url in urls {
+            *self.planned.entry(url_domain(url)).or_insert(0) += 1;
+        }
+        self
+    }
+
+    pub fn fetch_started(&self, domain: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        *inner.in_flight.entry(domain.to_string()).or_insert(0) += 1;
+    }
+
+    pub fn fetch_finished(&self, domain: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(count) = inner.in_flight.get_mut(domain) {
+            *count -= 1;
+            if *count == 0 {
+                inner.in_flight.remove(domain);
+            }
+        }
+    }
+
+    /// Counts the outcome for `url`. A `NO-TOKENS` failure marks its domain as out
+    /// of tokens. The run aborts once every domain with URLs left is out of tokens,
+    /// or when more than `--max-failures` URLs failed for other reasons.
+    pub fn record(&self, url: &str, strategy: Option<&'static str>, outcome: Outcome) {
+        let domain = url_domain(url);
+
+        let mut inner = self.inner.lock().unwrap();
+        inner.completed += 1;
+        inner.domains.entry(domain.clone()).or_default().add(outcome);
+        if let Some(strategy) = strategy {
+            inner.strategies.entry(strategy).or_default().add(outcome);
+        }
+
+        match outcome {
+            Outcome::Downloaded { bytes } => {
+                inner.downloaded += 1;
+                inner.bytes += bytes;
+            }
+            Outcome::Skipped => inner.skipped += 1,
+            Outcome::Failed(code) => {
+                inner.failed += 1;
+                *inner.failures_by_code.entry(code).or_insert(0) += 1;
+
+                if code == FetchErrorKind::NoTokens.code() {
+                    inner.exhausted_domains.insert(domain);
+                } else {
+                    let no_tokens = inner
+                        .failures_by_code
+                        .get(FetchErrorKind::NoTokens.code())
+                        .copied()
+                        .unwrap_or(0);
+                    if self.max_failures.is_some_and(|max| inner.failed - no_tokens > max) {
+                        inner.aborted.get_or_insert(AbortReason::MaxFailures);
+                    }
+                }
+            }
+        }
+
+        if !inner.exhausted_domains.is_empty() && self.only_exhausted_left(&inner) {
+            inner.aborted.get_or_insert(AbortReason::TokensExhausted);
+        }
+    }
+
+    /// Whether every domain with URLs not yet done is out of tokens.
+    fn only_exhausted_left(&self, inner: &StatsSnapshot) -> bool {
+        self.planned
+            .iter()
+            .filter(|(domain, planned)| {
+                inner.domains.get(*domain).map_or(0, OutcomeCounts::total) < **planned
+            })
+            .all(|(domain, _)| inner.exhausted_domains.contains(domain))
+    }
+
+    /// Whether `url`'s domain ran out of tokens earlier in the run.
+    pub fn is_exhausted(&self, url: &str) -> bool {
+        self.inner
+            .lock()
+            .unwrap()
+            .exhausted_domains
+            .contains(&url_domain(url))
+    }
+
+    /// Stops the run for `reason`, unless it was already aborted.
+    pub fn abort(&self, reason: AbortReason) {
+        self.inner.lock().unwrap().aborted.get_or_insert(reason);
+    }
+
+    pub fn is_aborted(&self) -> bool {
+        self.inner.lock().unwrap().aborted.is_some()
+    }
+
+    pub fn snapshot(&self) -> StatsSnapshot {
+        let mut snapshot = self.inner.lock().unwrap().clone();
+        snapshot.elapsed = self.started.elapsed();
+        snapshot
+    }
+}
+
+/// Host of `url`, as outcomes are counted by.
+fn url_domain(url: &str) -> String {
+    url::Url::parse(url)
+        .ok()
+        .and_then(|parsed| parsed.host_str().map(str::to_string))
+        .unwrap_or_else(|| "(invalid)".to_string())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenUsage {
+    pub domain: String,
+    pub token: String,
+    pub requests: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining: Option<u32>,
+    pub limit: u32,
+}
+
+/// End-of-run report, printed as text or emitted as the final JSON event.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunSummary {
+    pub status: RunStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aborted: Option<AbortReason>,
+    pub total: usize,
+    pub completed: usize,
+    pub downloaded: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub bytes: u64,
+    pub elapsed_secs: f64,
+    pub failures_by_code: BTreeMap<&'static str, usize>,
+    pub domains: BTreeMap<String, OutcomeCounts>,
+    pub strategies: BTreeMap<&'static str, OutcomeCounts>,
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub exhausted_domains: BTreeSet<String>,
+    pub tokens: Vec<TokenUsage>,
+}
+
+impl RunSummary {
+    /// Builds the summary from the final counters and each domain's tokens.
+    pub fn new(snapshot: StatsSnapshot, tokens: &[(String, Vec<Token>)]) -> Self {
+        let status = match snapshot.aborted {
+            Some(AbortReason::TokensExhausted) => RunStatus::TokensExhausted,
+            Some(AbortReason::Interrupted) => RunStatus::Interrupted,
+            Some(AbortReason::MaxFailures) => RunStatus::TooManyFailures,
+            _ if snapshot.failed > 0 => RunStatus::SomeFailed,
+            _ => RunStatus::Success,
+        };
+
+        let tokens = tokens
+            .iter()
+            .flat_map(|(domain, domain_tokens)| {
+                domain_tokens.iter().map(move |token| TokenUsage {
+                    domain: domain.clone(),
+                    token: mask_token(&token.value),
+                    requests: token.requests,
+                    remaining: token.remaining,
+                    limit: token.limit,
+                })
+            })
+            .collect();
+
+        Self {
+            status,
+            aborted: snapshot.aborted,
+            total: snapshot.total,
+            completed: snapshot.completed,
+            downloaded: snapshot.downloaded,
+            skipped: snapshot.skipped,
+            failed: snapshot.failed,
+            bytes: snapshot.bytes,
+            elapsed_secs: snapshot.elapsed.as_secs_f64(),
+            failures_by_code: snapshot.failures_by_code,
+            domains: snapshot.domains,
+            strategies: snapshot.strategies,
+            exhausted_domains: snapshot.exhausted_domains,
+            tokens,
+        }
+    }
+
+    /// Human-readable report, one line per entry.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "Summary: {} downloaded, {} skipped, {} failed of {} ({} not reached), {} bytes in {:.1}s",
+            self.downloaded,
+            self.skipped,
+            self.failed,
+            self.total,
+            self.total.saturating_sub(self.completed),
+            self.bytes,
+            self.elapsed_secs
+        )];
+
+        match self.aborted {
+            Some(AbortReason::TokensExhausted) => {
+                lines.push("Aborted: all tokens exhausted".to_string())
+            }
+            Some(AbortReason::MaxFailures) => {
+                lines.push("Aborted: too many failures".to_string())
+            }
+            Some(AbortReason::Interrupted) => lines.push("Aborted: interrupted".to_string()),
+            None => {}
+        }
+
+        if !self.exhausted_domains.is_empty() {
+            let domains: Vec<&str> = self.exhausted_domains.iter().map(String::as_str).collect();
+            lines.push(format!("Out of tokens, remaining urls skipped: {}", domains.join(", ")));
+        }
+
+        if !self.failures_by_code.is_empty() {
+            lines.push("Failures by code:".to_string());
+            for (code, count) in &self.failures_by_code {
+                lines.push(format!("  {:<16} {}", code, count));
+            }
+        }
+
+        let counts_line = |name: &str, counts: &OutcomeCounts| {
+            format!(
+                "  {:<24} {} downloaded, {} skipped, {} failed",
+                name, counts.downloaded, counts.skipped, counts.failed
+            )
+        };
+        if !self.domains.is_empty() {
+            lines.push("By domain:".to_string());
+            for (domain, counts) in &self.domains {
+                lines.push(counts_line(domain, counts));
+            }
+        }
+        if !self.strategies.is_empty() {
+            lines.push("By strategy:".to_string());
+            for (strategy, counts) in &self.strategies {
+                lines.push(counts_line(strategy, counts));
+            }
+        }
+        if !self.tokens.is_empty() {
+            lines.push("Tokens:".to_string());
+            for usage in &self.tokens {
+                let remaining = usage
+                    .remaining
+                    .map(|remaining| format!(", {}/{} remaining", remaining, usage.limit))
+                    .unwrap_or_default();
+                lines.push(format!(
+                    "  {:<16} {}  {} requests{}",
+                    usage.domain, usage.token, usage.requests, remaining
+                ));
+            }
+        }
+
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_flight_and_outcomes() {
+        let stats = RunStats::new(3, None);
+
+        stats.fetch_started("github.com");
+        stats.fetch_started("github.com");
+        stats.fetch_started("gitlab.com");
+        stats.fetch_finished("gitlab.com");
+        stats.record("https://github.com/a/b", Some("api"), Outcome::Downloaded { bytes: 120 });
+        stats.record("https://github.com/a/c", Some("api"), Outcome::Failed("NO-README"));
+        stats.record("https://gitlab.com/a/d", None, Outcome::Skipped);
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.completed, 3);
+        assert_eq!(snapshot.bytes, 120);
+        assert_eq!(snapshot.in_flight.get("github.com"), Some(&2));
+        assert!(!snapshot.in_flight.contains_key("gitlab.com"));
+        assert_eq!(snapshot.failures_by_code.get("NO-README"), Some(&1));
+        assert_eq!(snapshot.domains["github.com"].failed, 1);
+        assert_eq!(snapshot.strategies["api"].downloaded, 1);
+        assert!(!stats.is_aborted());
+
+        let summary = RunSummary::new(snapshot, &[]);
+        assert_eq!(summary.status, RunStatus::SomeFailed);
+    }
+
+    #[test]
+    fn test_abort_conditions() {
+        let urls = [
+            "https://github.com/a/b",
+            "https://github.com/a/x",
+            "https://github.com/a/c",
+            "https://codeberg.org/a/d",
+        ];
+        let stats = RunStats::new(urls.len(), Some(1)).with_urls(urls);
+        stats.record("https://github.com/a/b", None, Outcome::Failed("NO-README"));
+        // Skipped for missing tokens, which doesn't count towards --max-failures
+        stats.record("https://github.com/a/x", None, Outcome::Failed("NO-TOKENS"));
+        assert!(!stats.is_aborted());
+        stats.record("https://github.com/a/c", None, Outcome::Failed("NO-README"));
+        assert_eq!(stats.snapshot().aborted, Some(AbortReason::MaxFailures));
+        let summary = RunSummary::new(stats.snapshot(), &[]);
+        assert_eq!(summary.status, RunStatus::TooManyFailures);
+
+        let stats = RunStats::new(10, None);
+        stats.record("https://github.com/a/b", None, Outcome::Failed("NO-TOKENS"));
+        let summary = RunSummary::new(stats.snapshot(), &[]);
+        assert_eq!(summary.status, RunStatus::TokensExhausted);
+    }
+
+    #[test]
+    fn test_tokens_exhausted_per_domain() {
+        let urls = [
+            "https://github.com/a/b",
+            "https://github.com/a/c",
+            "https://codeberg.org/a/d",
+            "https://huggingface.co/e",
+        ];
+        let stats = RunStats::new(urls.len(), None).with_urls(urls);
+
+        // Other domains still have URLs left, so only github.com's are skipped
+        stats.record(urls[0], Some("api"), Outcome::Failed("NO-TOKENS"));
+        assert!(!stats.is_aborted());
+        assert!(stats.is_exhausted(urls[1]));
+        assert!(!stats.is_exhausted(urls[2]));
+
+        stats.record(urls[2], Some("api"), Outcome::Downloaded { bytes: 10 });
+        assert!(!stats.is_aborted());
+        stats.record(urls[3], Some("api"), Outcome::Failed("NO-TOKENS"));
+        assert_eq!(stats.snapshot().aborted, Some(AbortReason::TokensExhausted));
+    }
+}
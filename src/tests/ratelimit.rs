@@ -49,6 +49,26 @@ async fn test_rate_limiter_loads_tokens() {
     std::env::remove_var("TEST_TOKEN");
 }
 
+#[tokio::test]
+async fn test_rate_limiter_env_var_fallback() {
+    std::env::set_var("TEST_FALLBACK_TOKEN", "legacy1,legacy2");
+
+    let limiter = TokenRateLimiter::new("ARCHIVE_LIST_TOKEN_TEST_FALLBACK")
+        .with_fallback_env_var("TEST_FALLBACK_TOKEN");
+    limiter.load_tokens().await;
+    assert_eq!(limiter.token_count().await, 2);
+
+    // The host-scoped var wins over the legacy one once it is set
+    std::env::set_var("ARCHIVE_LIST_TOKEN_TEST_FALLBACK", "scoped");
+    limiter.load_tokens().await;
+    let tokens = limiter.snapshot().await;
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0].value, "scoped");
+
+    std::env::remove_var("ARCHIVE_LIST_TOKEN_TEST_FALLBACK");
+    std::env::remove_var("TEST_FALLBACK_TOKEN");
+}
+
 #[tokio::test]
 async fn test_rate_limiter_round_robin() {
     std::env::set_var("TEST_TOKEN", "token1,token2,token3");
@@ -213,3 +233,50 @@ async fn test_rate_limiter_all_tokens_exhausted() {
 
     std::env::remove_var("TEST_TOKEN");
 }
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_rate_limiter_reports_git_credential() {
+    use crate::util::{GitCredentials, TokenSources};
+    use std::os::unix::fs::PermissionsExt;
+
+    // Stands in for `git`: answers `credential fill` and logs every call
+    let dir = std::env::temp_dir().join(format!("archive-list-git-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let log = dir.join("calls.log");
+    let program = dir.join("git");
+    std::fs::write(
+        &program,
+        format!(
+            "#!/bin/sh\n\
+             input=$(cat)\n\
+             echo \"$2 $(echo \"$input\" | grep -c .)\" >> {log}\n\
+             if [ \"$2\" = fill ]; then printf 'protocol=https\\nhost=git.corp.example\\nusername=me\\npassword=helper_token\\n'; fi\n",
+            log = log.display()
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let limiter = TokenRateLimiter::new("TEST_GIT_CREDENTIAL_TOKEN").with_sources(TokenSources {
+        git_credential: Some(GitCredentials {
+            host: "git.corp.example".to_string(),
+            program,
+        }),
+        ..Default::default()
+    });
+    limiter.load_tokens().await;
+    assert_eq!(limiter.snapshot().await[0].value, "helper_token");
+
+    // Only the first success is approved; other tokens are not reported
+    limiter.report_credential("helper_token", true).await;
+    limiter.report_credential("helper_token", true).await;
+    limiter.report_credential("other_token", false).await;
+    limiter.report_credential("helper_token", false).await;
+
+    let calls = std::fs::read_to_string(&log).unwrap();
+    // fill sends protocol and host; approve and reject add username and password
+    assert_eq!(calls.lines().collect::<Vec<_>>(), ["fill 2", "approve 4", "reject 4"]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
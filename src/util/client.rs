@@ -1,27 +1,44 @@
+use crate::config::ProviderSettings;
 use reqgov::{ConcurrencyRateLimiter, OriginRegistry, ResponseAdapter, SmootherConfig};
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
 use std::sync::Arc;
 
-pub fn create_client() -> ClientWithMiddleware {
+pub fn create_client(settings: &ProviderSettings) -> ClientWithMiddleware {
+    let limits = &settings.limits;
+
     let smoother_config = SmootherConfig {
-        micro_interval_secs: 2,
-        velocity: 1.5,
+        micro_interval_secs: limits.micro_interval_secs,
+        velocity: limits.velocity,
     };
 
     let origin_registry = OriginRegistry::builder().smoother(smoother_config).build();
 
+    // Per-domain overrides are enforced by each provider; the shared limiter only
+    // needs to allow the largest of them
     let concurrency_limiter = ConcurrencyRateLimiter::builder()
-        .max_concurrent_global(10)
-        .max_concurrent_per_domain(2)
+        .max_concurrent_global(limits.max_concurrent_global)
+        .max_concurrent_per_domain(settings.max_concurrent_any_domain())
         .build();
 
-    ClientBuilder::new(reqwest::Client::new())
+    let mut http = reqwest::Client::builder();
+    if let Some(timeout) = limits.request_timeout() {
+        http = http.timeout(timeout);
+    }
+    if let Some(timeout) = limits.connect_timeout() {
+        http = http.connect_timeout(timeout);
+    }
+    let http = http.build().unwrap_or_else(|e| {
+        log::warn!("Failed to build HTTP client with timeouts, using defaults: {}", e);
+        reqwest::Client::new()
+    });
+
+    ClientBuilder::new(http)
         .with(origin_registry)
         .with(ResponseAdapter)
         .with(concurrency_limiter)
         .build()
 }
 
-pub fn create_shared_client() -> Arc<ClientWithMiddleware> {
-    Arc::new(create_client())
+pub fn create_shared_client(settings: &ProviderSettings) -> Arc<ClientWithMiddleware> {
+    Arc::new(create_client(settings))
 }
@@ -0,0 +1,212 @@
+use anyhow::{bail, Context, Result};
+use std::path::PathBuf;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+
+/// Where a credential was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CredentialOrigin {
+    GitHelper,
+    Netrc,
+}
+
+/// A username and password for a host, the password being used as the token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credential {
+    pub host: String,
+    pub username: Option<String>,
+    pub password: String,
+    pub origin: CredentialOrigin,
+}
+
+/// Asks git's configured credential helpers for a host's credentials, falling back
+/// to `~/.netrc` (or `$NETRC`), and reports back whether they worked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitCredentials {
+    pub host: String,
+    /// The `git` executable, replaceable by a fake in tests.
+    pub program: PathBuf,
+}
+
+impl GitCredentials {
+    pub fn new(host: impl Into<String>) -> Self {
+        Self {
+            host: host.into(),
+            program: PathBuf::from("git"),
+        }
+    }
+
+    /// The host's credential from `git credential fill`, else from netrc.
+    pub async fn find(&self) -> Option<Credential> {
+        match self.fill().await {
+            Ok(Some(credential)) => return Some(credential),
+            Ok(None) => log::debug!("git credential has nothing for {}", self.host),
+            Err(e) => log::debug!("git credential fill failed for {}: {:#}", self.host, e),
+        }
+
+        match netrc_credential(&self.host) {
+            Ok(found) => found,
+            Err(e) => {
+                log::warn!("Skipping netrc for {}: {:#}", self.host, e);
+                None
+            }
+        }
+    }
+
+    /// Runs `git credential fill` for `https://<host>`, without prompting.
+    pub async fn fill(&self) -> Result<Option<Credential>> {
+        let output = self.run("fill", &self.describe(None)).await?;
+
+        let mut username = None;
+        let mut password = None;
+        for line in output.lines() {
+            match line.split_once('=') {
+                Some(("username", value)) => username = Some(value.to_string()),
+                Some(("password", value)) => password = Some(value.to_string()),
+                _ => {}
+            }
+        }
+
+        Ok(password.filter(|p| !p.is_empty()).map(|password| Credential {
+            host: self.host.clone(),
+            username,
+            password,
+            origin: CredentialOrigin::GitHelper,
+        }))
+    }
+
+    /// Tells the helpers the credential worked, so they can store it.
+    pub async fn approve(&self, credential: &Credential) -> Result<()> {
+        self.run("approve", &self.describe(Some(credential))).await.map(|_| ())
+    }
+
+    /// Tells the helpers the credential was refused, so they can forget it.
+    pub async fn reject(&self, credential: &Credential) -> Result<()> {
+        self.run("reject", &self.describe(Some(credential))).await.map(|_| ())
+    }
+
+    /// The credential description git reads on stdin.
+    fn describe(&self, credential: Option<&Credential>) -> String {
+        let mut input = format!("protocol=https\nhost={}\n", self.host);
+        if let Some(credential) = credential {
+            if let Some(username) = &credential.username {
+                input.push_str(&format!("username={}\n", username));
+            }
+            input.push_str(&format!("password={}\n", credential.password));
+        }
+        input.push('\n');
+        input
+    }
+
+    async fn run(&self, action: &str, input: &str) -> Result<String> {
+        let mut child = tokio::process::Command::new(&self.program)
+            .args(["credential", action])
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to run {} credential {}", self.program.display(), action))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(input.as_bytes()).await?;
+        }
+
+        let output = child.wait_with_output().await?;
+        if !output.status.success() {
+            bail!(
+                "git credential {} exited with {}: {}",
+                action,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+/// The `machine <host>` entry of `$NETRC` or `~/.netrc`.
+pub fn netrc_credential(host: &str) -> Result<Option<Credential>> {
+    let path = match std::env::var_os("NETRC") {
+        Some(path) => PathBuf::from(path),
+        None => match directories::BaseDirs::new() {
+            Some(dirs) => dirs.home_dir().join(".netrc"),
+            None => return Ok(None),
+        },
+    };
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(parse_netrc(&text, host))
+}
+
+/// Finds `host` in netrc text. The catch-all `default` entry is never used: its
+/// password is meant for some other service, not a forge token.
+fn parse_netrc(text: &str, host: &str) -> Option<Credential> {
+    #[derive(Default)]
+    struct Entry {
+        machine: Option<String>,
+        login: Option<String>,
+        password: Option<String>,
+    }
+
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut words = text.split_whitespace();
+    while let Some(word) = words.next() {
+        match word {
+            "machine" => entries.push(Entry {
+                machine: words.next().map(str::to_string),
+                ..Default::default()
+            }),
+            "default" => entries.push(Entry::default()),
+            "login" | "password" | "account" => {
+                let value = words.next().map(str::to_string);
+                if let Some(entry) = entries.last_mut() {
+                    match word {
+                        "login" => entry.login = value,
+                        "password" => entry.password = value,
+                        _ => {}
+                    }
+                }
+            }
+            // Macro bodies run to a blank line, which whitespace splitting loses;
+            // nothing after one is read
+            "macdef" => break,
+            _ => {}
+        }
+    }
+
+    let entry = entries
+        .iter()
+        .find(|entry| entry.machine.as_deref() == Some(host))?;
+
+    Some(Credential {
+        host: host.to_string(),
+        username: entry.login.clone(),
+        password: entry.password.clone()?,
+        origin: CredentialOrigin::Netrc,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_netrc() {
+        let netrc = "machine github.com login me password ghp_one\n\
+                     machine git.corp.example\n  login bot\n  password glpat_two\n\
+                     default login anon password anything\n";
+
+        let github = parse_netrc(netrc, "github.com").unwrap();
+        assert_eq!(github.username.as_deref(), Some("me"));
+        assert_eq!(github.password, "ghp_one");
+        assert_eq!(parse_netrc(netrc, "git.corp.example").unwrap().password, "glpat_two");
+        assert_eq!(parse_netrc(netrc, "codeberg.org"), None);
+        assert_eq!(parse_netrc("machine github.com login me", "github.com"), None);
+    }
+}
@@ -1,13 +1,16 @@
 pub mod client;
+pub mod credential;
 pub mod provider_selector;
 pub mod ratelimit_headers;
 pub mod reader;
 pub mod token;
+pub mod token_source;
 
 pub use client::create_shared_client;
-pub use provider_selector::get_provider_factory;
-pub use reader::ReverseBufferReader;
+pub use credential::GitCredentials;
+pub use provider_selector::{configure_providers, get_provider_factory};
+pub use reader::{ForwardBufferReader, LineReader, ReverseBufferReader};
 
-#[allow(dead_code, unused_imports)]
 pub use ratelimit_headers::{detect_rate_limits, has_rate_limit_headers, RateLimitInfo};
-pub use token::{Token, TokenRateLimiter};
+pub use token::{mask_token, Token, TokenRateLimiter};
+pub use token_source::TokenSources;
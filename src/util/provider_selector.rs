@@ -1,11 +1,23 @@
+use crate::config::ProviderSettings;
 use crate::provider::ProviderFactory;
+use std::sync::OnceLock;
 use tokio::sync::OnceCell;
 
 static FACTORY: OnceCell<ProviderFactory> = OnceCell::const_new();
+static SETTINGS: OnceLock<ProviderSettings> = OnceLock::new();
+
+/// Sets the limits and per-domain overrides the shared factory is built with. Has
+/// no effect once the factory exists.
+pub fn configure_providers(settings: ProviderSettings) {
+    if SETTINGS.set(settings).is_err() {
+        log::debug!("Provider settings already configured, ignoring");
+    }
+}
 
 pub async fn get_provider_factory() -> &'static ProviderFactory {
     FACTORY
-        .get_or_init(|| async { ProviderFactory::new() })
+        .get_or_init(|| async {
+            ProviderFactory::with_settings(SETTINGS.get().cloned().unwrap_or_default())
+        })
         .await
 }
-
@@ -1,6 +1,16 @@
 use anyhow::{Context, Result};
 use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+const CHUNK_SIZE: usize = 4096;
+
+/// Line-at-a-time reading of the archlist, in whichever direction it is processed.
+pub trait LineReader {
+    fn read_line(&mut self) -> Result<Option<String>>;
+
+    fn lines_read(&self) -> usize;
+}
 
 pub struct ReverseBufferReader {
     file: File,
@@ -14,10 +24,12 @@ pub struct ReverseBufferReader {
 }
 
 impl ReverseBufferReader {
-    pub fn new(path: &str) -> Result<Self> {
-        let file = File::open(path).context("Failed to open file")?;
+    pub fn new(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open file: {}", path.display()))?;
         let file_size = file.metadata()?.len();
-        let chunk_size = 4096;
+        let chunk_size = CHUNK_SIZE;
 
         Ok(Self {
             file,
@@ -29,8 +41,10 @@ impl ReverseBufferReader {
             lines_read: 0,
         })
     }
+}
 
-    pub fn read_line(&mut self) -> Result<Option<String>> {
+impl LineReader for ReverseBufferReader {
+    fn read_line(&mut self) -> Result<Option<String>> {
         if self.file_pos == 0 && self.buffer_pos == 0 {
             return Ok(None);
         }
@@ -73,4 +87,47 @@ impl ReverseBufferReader {
         let line = String::from_utf8(bytes).context("Invalid UTF-8")?;
         Ok(Some(line))
     }
+
+    fn lines_read(&self) -> usize {
+        self.lines_read
+    }
+}
+
+pub struct ForwardBufferReader {
+    reader: BufReader<File>,
+    lines_read: usize,
+}
+
+impl ForwardBufferReader {
+    pub fn new(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open file: {}", path.display()))?;
+
+        Ok(Self {
+            reader: BufReader::with_capacity(CHUNK_SIZE, file),
+            lines_read: 0,
+        })
+    }
+}
+
+impl LineReader for ForwardBufferReader {
+    fn read_line(&mut self) -> Result<Option<String>> {
+        let mut bytes = Vec::new();
+        if self.reader.read_until(b'\n', &mut bytes)? == 0 {
+            return Ok(None);
+        }
+
+        while matches!(bytes.last(), Some(b'\n') | Some(b'\r')) {
+            bytes.pop();
+        }
+
+        self.lines_read += 1;
+        let line = String::from_utf8(bytes).context("Invalid UTF-8")?;
+        Ok(Some(line))
+    }
+
+    fn lines_read(&self) -> usize {
+        self.lines_read
+    }
 }
@@ -1,6 +1,9 @@
+use super::credential::{Credential, CredentialOrigin};
+use super::token_source::TokenSources;
+use crate::provider::domain::AuthScheme;
 use chrono::{DateTime, Utc};
-use reqwest::Client;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use reqwest_middleware::ClientWithMiddleware;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -11,6 +14,8 @@ pub struct Token {
     pub remaining: Option<u32>,
     pub limit: u32,
     pub reset_at: Option<DateTime<Utc>>,
+    /// Requests this token was handed out for during the run.
+    pub requests: u64,
 }
 
 impl Token {
@@ -21,6 +26,7 @@ impl Token {
             remaining: None,
             limit: 0,
             reset_at: None,
+            requests: 0,
         }
     }
 
@@ -37,25 +43,69 @@ impl Token {
 pub struct TokenRateLimiter {
     tokens: Arc<RwLock<Vec<Token>>>,
     current_index: Arc<AtomicUsize>,
-    env_var_name: &'static str,
+    /// Env vars in lookup order; the first one set is used.
+    env_vars: Vec<String>,
+    sources: TokenSources,
+    /// The git or netrc credential in the pool, reported back to git's helpers.
+    credential: RwLock<Option<Credential>>,
+    credential_approved: AtomicBool,
 }
 
 impl TokenRateLimiter {
-    pub fn new(env_var_name: &'static str) -> Self {
+    pub fn new(env_var: impl Into<String>) -> Self {
         Self {
             tokens: Arc::new(RwLock::new(Vec::new())),
             current_index: Arc::new(AtomicUsize::new(0)),
-            env_var_name,
+            env_vars: vec![env_var.into()],
+            sources: TokenSources::default(),
+            credential: RwLock::new(None),
+            credential_approved: AtomicBool::new(false),
         }
     }
 
+    /// Reads `env_var` when the env vars before it are unset or empty.
+    pub fn with_fallback_env_var(mut self, env_var: impl Into<String>) -> Self {
+        self.env_vars.push(env_var.into());
+        self
+    }
+
+    /// Also reads tokens from `sources` on load.
+    pub fn with_sources(mut self, sources: TokenSources) -> Self {
+        self.sources = sources;
+        self
+    }
+
+    /// Loads the comma-separated tokens in the first env var that has any, then
+    /// those of every other source, into one pool. A token found in several places
+    /// is kept once.
     pub async fn load_tokens(&self) {
-        let token_values: Vec<String> = std::env::var(self.env_var_name)
-            .unwrap_or_default()
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect();
+        let mut token_values: Vec<String> = self
+            .env_vars
+            .iter()
+            .map(|name| {
+                std::env::var(name)
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .find(|values| !values.is_empty())
+            .unwrap_or_default();
+
+        for value in self.sources.load().await {
+            if !token_values.contains(&value) {
+                token_values.push(value);
+            }
+        }
+
+        let credential = self.sources.load_credential().await;
+        if let Some(credential) = &credential {
+            if !token_values.contains(&credential.password) {
+                token_values.push(credential.password.clone());
+            }
+        }
+        *self.credential.write().await = credential;
 
         let tokens: Vec<Token> = token_values.into_iter().map(Token::new).collect();
 
@@ -80,12 +130,13 @@ impl TokenRateLimiter {
             return None;
         }
 
-        let tokens = self.tokens.read().await;
+        let mut tokens = self.tokens.write().await;
         let mut attempts = 0;
         while attempts < token_count {
             let index = self.current_index.fetch_add(1, Ordering::SeqCst) % token_count;
-            if let Some(token) = tokens.get(index) {
+            if let Some(token) = tokens.get_mut(index) {
                 if token.is_available() {
+                    token.requests += 1;
                     return Some(token.value.clone());
                 }
             }
@@ -119,6 +170,34 @@ impl TokenRateLimiter {
         }
     }
 
+    /// Tells git's credential helpers whether the token they gave us worked: the
+    /// first success approves it, a rejection makes them forget it. Tokens from
+    /// elsewhere are ignored.
+    pub async fn report_credential(&self, token_value: &str, accepted: bool) {
+        let Some(git) = &self.sources.git_credential else {
+            return;
+        };
+        let credential = self.credential.read().await;
+        let Some(credential) = credential
+            .as_ref()
+            .filter(|c| c.origin == CredentialOrigin::GitHelper && c.password == token_value)
+        else {
+            return;
+        };
+
+        let result = if accepted {
+            if self.credential_approved.swap(true, Ordering::SeqCst) {
+                return;
+            }
+            git.approve(credential).await
+        } else {
+            git.reject(credential).await
+        };
+        if let Err(e) = result {
+            log::warn!("Failed to report credential for {} to git: {:#}", credential.host, e);
+        }
+    }
+
     pub async fn mark_rate_limited(&self, token_value: &str, reset_at: Option<DateTime<Utc>>) {
         let mut tokens = self.tokens.write().await;
         if let Some(token) = tokens.iter_mut().find(|t| t.value == token_value) {
@@ -151,11 +230,23 @@ impl TokenRateLimiter {
         tokens.iter().all(|t| !t.is_available())
     }
 
+    /// Current state of every loaded token, in rotation order.
+    pub async fn snapshot(&self) -> Vec<Token> {
+        self.tokens.read().await.clone()
+    }
+
     pub async fn token_count(&self) -> usize {
         self.tokens.read().await.len()
     }
 
-    pub async fn validate_all_tokens(&self, validation_url: &str) {
+    /// Checks every token against `validation_url`, sending it the way `auth` says,
+    /// through the same client (timeouts and pacing) as every other request.
+    pub async fn validate_all_tokens(
+        &self,
+        client: &Arc<ClientWithMiddleware>,
+        validation_url: &str,
+        auth: &AuthScheme,
+    ) {
         let tokens = self.tokens.read().await;
         if tokens.is_empty() {
             return;
@@ -173,14 +264,14 @@ impl TokenRateLimiter {
             return;
         }
 
-        let client = Client::new();
         let handles: Vec<_> = tokens.iter().map(|token| {
-            let client = client.clone();
+            let client = Arc::clone(client);
             let token_value = token.value.clone();
             let validation_url = validation_url.to_string();
+            let (header, value) = auth.header(&token_value);
 
             tokio::spawn(async move {
-                Self::validate_token(&client, &token_value, &validation_url).await
+                Self::validate_token(&client, &header, &value, &validation_url).await
             })
         }).collect();
         drop(tokens);
@@ -204,12 +295,13 @@ impl TokenRateLimiter {
     }
 
     async fn validate_token(
-        client: &Client,
-        token_value: &str,
+        client: &ClientWithMiddleware,
+        header: &str,
+        value: &str,
         validation_url: &str,
     ) -> Option<super::ratelimit_headers::RateLimitInfo> {
         let mut request = client.get(validation_url);
-        request = request.header("Authorization", format!("Bearer {}", token_value));
+        request = request.header(header, value);
 
         let response = request.send().await.ok()?;
 
@@ -220,3 +312,27 @@ impl TokenRateLimiter {
         Some(super::ratelimit_headers::detect_rate_limits(response.headers()))
     }
 }
+
+/// Shortens a token for display, keeping only enough to tell tokens apart.
+pub fn mask_token(token: &str) -> String {
+    let chars: Vec<char> = token.chars().collect();
+    if chars.len() < 12 {
+        return "*".repeat(chars.len().max(4));
+    }
+
+    let head: String = chars[..4].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}…{}", head, tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_token() {
+        assert_eq!(mask_token("ghp_abcdefghijklmnopwxyz"), "ghp_…wxyz");
+        assert_eq!(mask_token("short"), "*****");
+        assert_eq!(mask_token(""), "****");
+    }
+}
@@ -0,0 +1,226 @@
+use super::credential::{Credential, GitCredentials};
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+/// `service` attribute archive-list's keyring entries are stored under.
+pub const KEYRING_SERVICE: &str = "archive-list";
+
+/// Where a domain's tokens come from besides its env var. Tokens from every source
+/// go into the same rotation pool.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TokenSources {
+    /// File with one token per line. Must not be accessible to group or others.
+    pub file: Option<PathBuf>,
+    /// Shell command printing one token per line, e.g. `pass show forge/github`.
+    pub command: Option<String>,
+    /// Host whose tokens are looked up in the Secret Service keyring, stored with
+    /// the attributes `service=archive-list host=<host>`.
+    pub keyring_host: Option<String>,
+    /// Credentials from git's credential helpers or `~/.netrc`. The password is
+    /// used as the token.
+    pub git_credential: Option<GitCredentials>,
+}
+
+impl TokenSources {
+    /// Reads tokens from every configured source. A source that fails is logged
+    /// and skipped, so the others still count.
+    pub async fn load(&self) -> Vec<String> {
+        let mut tokens = Vec::new();
+
+        if let Some(path) = &self.file {
+            match read_token_file(path) {
+                Ok(found) => {
+                    log::debug!("Read {} tokens from {}", found.len(), path.display());
+                    tokens.extend(found);
+                }
+                Err(e) => log::warn!("Skipping token file {}: {:#}", path.display(), e),
+            }
+        }
+
+        if let Some(command) = &self.command {
+            match run_token_command(command).await {
+                Ok(found) => {
+                    log::debug!("Read {} tokens from token command", found.len());
+                    tokens.extend(found);
+                }
+                Err(e) => log::warn!("Skipping token command `{}`: {:#}", command, e),
+            }
+        }
+
+        if let Some(host) = &self.keyring_host {
+            match read_keyring(host).await {
+                Ok(found) => {
+                    log::debug!("Read {} tokens for {} from the keyring", found.len(), host);
+                    tokens.extend(found);
+                }
+                Err(e) => log::warn!("Skipping keyring for {}: {:#}", host, e),
+            }
+        }
+
+        tokens
+    }
+
+    /// Asks git, then netrc, for the domain's credential, if enabled.
+    pub async fn load_credential(&self) -> Option<Credential> {
+        let credential = self.git_credential.as_ref()?.find().await?;
+        log::debug!("Read a token for {} from {:?}", credential.host, credential.origin);
+        Some(credential)
+    }
+
+    /// Short description of the configured sources, for `explain`.
+    pub fn describe(&self) -> Vec<String> {
+        let mut sources = Vec::new();
+        if let Some(path) = &self.file {
+            sources.push(format!("file {}", path.display()));
+        }
+        if let Some(command) = &self.command {
+            sources.push(format!("command `{}`", command));
+        }
+        if let Some(host) = &self.keyring_host {
+            sources.push(format!("keyring service={} host={}", KEYRING_SERVICE, host));
+        }
+        if let Some(git) = &self.git_credential {
+            sources.push(format!("git credential for {}, then netrc", git.host));
+        }
+        sources
+    }
+}
+
+/// One token per line, skipping blank lines and `#` comments.
+fn parse_tokens(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+fn read_token_file(path: &Path) -> Result<Vec<String>> {
+    let path = expand_home(path);
+    check_permissions(&path)?;
+
+    let text = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(parse_tokens(&text))
+}
+
+/// Refuses token files other users could read, like ssh does with keys.
+#[cfg(unix)]
+fn check_permissions(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = fs::metadata(path)
+        .with_context(|| format!("Failed to stat {}", path.display()))?
+        .permissions()
+        .mode()
+        & 0o777;
+    if mode & 0o077 != 0 {
+        bail!(
+            "permissions {:04o} are too open, it must only be accessible by its owner (chmod 600)",
+            mode
+        );
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_permissions(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), directories::BaseDirs::new()) {
+        (Ok(rest), Some(dirs)) => dirs.home_dir().join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Runs `command` with `sh -c`. Its stderr goes to ours, so prompts and errors
+/// from e.g. `pass` stay visible.
+async fn run_token_command(command: &str) -> Result<Vec<String>> {
+    let output = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .output()
+        .await
+        .context("Failed to run")?;
+
+    if !output.status.success() {
+        bail!("exited with {}", output.status);
+    }
+
+    Ok(parse_tokens(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Reads tokens stored in the Secret Service for `host` over D-Bus, unlocking
+/// items as needed. Each secret may hold several tokens, one per line.
+#[cfg(target_os = "linux")]
+async fn read_keyring(host: &str) -> Result<Vec<String>> {
+    use secret_service::{EncryptionType, SecretService};
+    use std::collections::HashMap;
+
+    let service = SecretService::connect(EncryptionType::Dh)
+        .await
+        .context("Failed to connect to the Secret Service")?;
+    let items = service
+        .search_items(HashMap::from([("service", KEYRING_SERVICE), ("host", host)]))
+        .await
+        .context("Failed to search the keyring")?;
+
+    let mut tokens = Vec::new();
+    for item in items.unlocked.iter().chain(items.locked.iter()) {
+        if item.is_locked().await? {
+            item.unlock().await.context("Failed to unlock keyring item")?;
+        }
+        let secret = item.get_secret().await.context("Failed to read keyring item")?;
+        tokens.extend(parse_tokens(&String::from_utf8_lossy(&secret)));
+    }
+    Ok(tokens)
+}
+
+#[cfg(not(target_os = "linux"))]
+async fn read_keyring(_host: &str) -> Result<Vec<String>> {
+    bail!("the Secret Service keyring is only supported on Linux")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tokens() {
+        assert_eq!(
+            parse_tokens("ghp_one\n\n  # work account\nghp_two  \n"),
+            vec!["ghp_one", "ghp_two"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_token_file_must_be_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("archive-list-tokens-{}", std::process::id()));
+        fs::write(&path, "tok1\ntok2\n").unwrap();
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(read_token_file(&path).is_err());
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        assert_eq!(read_token_file(&path).unwrap(), vec!["tok1", "tok2"]);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_token_command() {
+        assert_eq!(
+            run_token_command("printf 'tok1\\ntok2\\n'").await.unwrap(),
+            vec!["tok1", "tok2"]
+        );
+        assert!(run_token_command("exit 3").await.is_err());
+    }
+}